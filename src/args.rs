@@ -23,6 +23,23 @@ pub enum Commands {
     Article(ArticleArgs),
     #[command(name = "clear", about = "Clears dist and cache directories.")]
     Clear(BuildArgs),
+    #[command(name = "theme", about = "Theme maintenance commands.")]
+    Theme(ThemeArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ThemeArgs {
+    #[command(subcommand)]
+    pub command: ThemeCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ThemeCommands {
+    #[command(
+        name = "check",
+        about = "Validate the theme and warn about missing translations."
+    )]
+    Check(BuildArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -1,11 +1,15 @@
-use std::{collections::HashMap, fs::File, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, fmt::Display, fs::File, path::PathBuf, sync::Arc};
 
+use itertools::Itertools;
 use linked_hash_map::LinkedHashMap;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
 use tracing::{debug, info};
 use url::Url;
 
-use crate::diagnostic::{BarDiagnostic, ContextExt};
+use crate::{
+    diagnostic::{BarDiagnostic, ContextExt},
+    metadata::Metadata,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -42,6 +46,95 @@ fn default_image_output_dir() -> String {
     "images".to_string()
 }
 
+fn default_words_per_minute() -> usize {
+    crate::pages::DEFAULT_WORDS_PER_MINUTE
+}
+
+fn default_excerpt_words() -> usize {
+    crate::excerpt::DEFAULT_EXCERPT_WORDS
+}
+
+fn default_section_template() -> Arc<str> {
+    Arc::from("section.html")
+}
+
+/// Type of a custom article metadata field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    /// RFC 3339 date time or `YYYY-MM-DD` date.
+    Date,
+    List,
+    Map,
+}
+
+impl FieldType {
+    #[must_use]
+    pub fn matches(&self, value: &serde_yaml::Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Float => value.is_number(),
+            Self::Boolean => value.is_bool(),
+            Self::Date => value.as_str().is_some_and(|date| {
+                chrono::DateTime::parse_from_rfc3339(date).is_ok()
+                    || chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()
+            }),
+            Self::List => value.is_sequence(),
+            Self::Map => value.is_mapping(),
+        }
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Float => "float",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+            Self::List => "list",
+            Self::Map => "map",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Tag {
+    /// Display name, defaults to the tag as first written in an article.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<Arc<str>>,
+    /// Absolute URL or path relative to the site root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<Arc<str>>,
+    /// Other spellings folded into this tag, e.g. `rustlang` for `rust`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Author {
+    pub name: Arc<str>,
+    /// Home page of the author.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<Url>,
+    /// Absolute URL or path relative to the site root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio: Option<Arc<str>>,
+    /// Used in the RSS `author` field, which must contain an email address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<Arc<str>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YamdProcessors {
     /// generate alt text for images
@@ -82,6 +175,67 @@ pub struct GpxEmbeddingConfig {
     pub attribution_png: Option<PathBuf>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PrettyUrls {
+    /// Write a redirect stub at the old `<pid>.html` location of every content page, so
+    /// existing links keep working.
+    /// Default: false
+    #[serde(default)]
+    pub redirects: bool,
+}
+
+/// Weights of the signals combined by `get_similar`. The score of a page is the weighted
+/// average of the signals, each between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SimilarityConfig {
+    /// Weight of shared tags, rare tags counting more than common ones.
+    /// Default: 1.0
+    #[serde(
+        default = "default_similarity_tags",
+        deserialize_with = "non_negative_weight"
+    )]
+    pub tags: f64,
+    /// Weight of TF-IDF similarity of the page text. `0` skips indexing the text.
+    /// Default: 0.0
+    #[serde(default, deserialize_with = "non_negative_weight")]
+    pub content: f64,
+}
+
+fn default_similarity_tags() -> f64 {
+    1.0
+}
+
+fn non_negative_weight<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let weight = f64::deserialize(deserializer)?;
+    if weight.is_nan() || weight < 0.0 {
+        return Err(D::Error::custom(format!(
+            "weight must be 0 or greater, found {weight}"
+        )));
+    }
+    Ok(weight)
+}
+
+impl Default for SimilarityConfig {
+    fn default() -> Self {
+        SimilarityConfig {
+            tags: default_similarity_tags(),
+            content: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedirectManifests {
+    /// Write alias redirects to a `_redirects` file (Netlify, Cloudflare Pages) in the dist root.
+    /// Default: false
+    #[serde(default)]
+    pub redirects_file: bool,
+    /// Write alias redirects as an nginx `map` snippet to `redirects.nginx.conf` in the dist root.
+    /// Default: false
+    #[serde(default)]
+    pub nginx_map: bool,
+}
+
 impl Default for GpxEmbeddingConfig {
     fn default() -> Self {
         GpxEmbeddingConfig {
@@ -110,14 +264,145 @@ pub struct Config {
     pub domain: Arc<Url>,
     pub title: Arc<str>,
     pub description: Arc<str>,
+    /// Default content language.
     #[serde(default = "default_language")]
     pub language: Arc<str>,
+    /// Other content languages. `post.de.yamd` is the `de` version of `post.yamd`.
+    #[serde(default)]
+    pub languages: Vec<Arc<str>>,
     pub template_config: HashMap<Arc<str>, TemplateConfigValue>,
     /// pre render yamd transformations
     pub yamd_processors: YamdProcessors,
     /// gpx embedding configuration
     #[serde(default)]
     pub gpx_embedding: GpxEmbeddingConfig,
+    /// If set, content pages are written as `/<pid>/index.html` and linked as `/<pid>/`.
+    /// Default: None (`/<pid>.html`)
+    #[serde(default)]
+    pub pretty_urls: Option<PrettyUrls>,
+    /// host specific redirect manifests for page aliases
+    #[serde(default)]
+    pub redirect_manifests: RedirectManifests,
+    /// Types of custom article metadata fields. Fields that are not listed are not checked.
+    #[serde(default)]
+    pub front_matter_schema: HashMap<Arc<str>, FieldType>,
+    /// Authors registry, keyed by the id used in article metadata.
+    #[serde(default)]
+    pub authors: HashMap<Arc<str>, Author>,
+    /// Tags registry, keyed by tag. Keys and aliases are case-insensitive.
+    #[serde(default)]
+    pub tags: HashMap<Arc<str>, Tag>,
+    /// URL pattern of articles, e.g. `/{year}/{month}/{slug}`. Supports `{year}`, `{month}`,
+    /// `{day}`, `{section}` (content subdirectory) and `{slug}` (`slug` metadata or file name).
+    /// Default: None (content file path)
+    #[serde(default)]
+    pub permalink: Option<Arc<str>>,
+    /// Template of section index pages for content subdirectories. Used when the section has
+    /// no `_index.yamd` naming a template, and only if the template exists.
+    /// Default: `section.html`
+    #[serde(default = "default_section_template")]
+    pub section_template: Arc<str>,
+    /// Reading speed used to estimate `reading_time` of pages.
+    /// Default: 200
+    #[serde(default = "default_words_per_minute")]
+    pub words_per_minute: usize,
+    /// Length in words of excerpts derived from content without a break marker.
+    /// Default: 50
+    #[serde(default = "default_excerpt_words")]
+    pub excerpt_words: usize,
+    /// If set, links between content pages are written as JSON to this path in `dist_path`.
+    /// Default: None
+    #[serde(default)]
+    pub link_graph: Option<Arc<str>>,
+    /// Weights of tag and content similarity used by `get_similar`.
+    #[serde(default)]
+    pub similarity: SimilarityConfig,
+}
+
+/// Non-empty segments joined with `/`, with a leading `/`.
+fn join_path(path: &str) -> String {
+    format!(
+        "/{}",
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .join("/")
+    )
+}
+
+impl Config {
+    /// URL path of the content page with the given pid.
+    #[must_use]
+    pub fn page_path(&self, pid: &str) -> String {
+        if let Some(section) = pid.strip_suffix("/_index") {
+            return self.section_path(section);
+        }
+        if self.pretty_urls.is_some() {
+            format!("{pid}/")
+        } else {
+            format!("{pid}.html")
+        }
+    }
+
+    /// URL path of the content page: `permalink` pattern for articles, file path with the file
+    /// name replaced by `slug` for other pages. Pages not in the default language are published
+    /// under `/<lang>/`, unless the pattern places `{lang}` itself.
+    #[must_use]
+    pub fn permalink(&self, pid: &str, metadata: &Metadata) -> String {
+        let (pid, _) = self.split_language(pid);
+        let lang = metadata.lang.as_deref().unwrap_or(&self.language);
+        let prefix = if lang == self.language.as_ref() {
+            ""
+        } else {
+            lang
+        };
+        let (section, name) = pid.rsplit_once('/').unwrap_or(("", pid));
+        if name == "_index" {
+            return self.section_path(&join_path(&format!("{prefix}/{section}")));
+        }
+        let slug = metadata
+            .slug
+            .as_deref()
+            .map_or(name, |slug| slug.trim_matches('/'));
+        let stem = match &self.permalink {
+            Some(pattern) if metadata.is_article() => {
+                let stem = pattern
+                    .replace("{year}", &metadata.date.format("%Y").to_string())
+                    .replace("{month}", &metadata.date.format("%m").to_string())
+                    .replace("{day}", &metadata.date.format("%d").to_string())
+                    .replace("{section}", section.trim_matches('/'))
+                    .replace("{slug}", slug);
+                if pattern.contains("{lang}") {
+                    stem.replace("{lang}", prefix)
+                } else {
+                    format!("{prefix}/{stem}")
+                }
+            }
+            _ => format!("{prefix}/{section}/{slug}"),
+        };
+        self.page_path(&join_path(&stem))
+    }
+
+    /// Split the language suffix of `languages` off the pid: `/post.de` is `/post` in `de`.
+    #[must_use]
+    pub fn split_language<'a>(&self, pid: &'a str) -> (&'a str, Option<&'a str>) {
+        match pid.rsplit_once('.') {
+            Some((base, lang)) if self.languages.iter().any(|known| known.as_ref() == lang) => {
+                (base, Some(lang))
+            }
+            _ => (pid, None),
+        }
+    }
+
+    /// URL path of the index page of a content directory, `/` for the content root.
+    #[must_use]
+    pub fn section_path(&self, section: &str) -> String {
+        let section = section.trim_end_matches('/');
+        if section.is_empty() {
+            "/".to_string()
+        } else {
+            self.page_path(section)
+        }
+    }
 }
 
 impl TryFrom<&PathBuf> for Config {
@@ -135,6 +420,7 @@ impl TryFrom<&PathBuf> for Config {
 #[cfg(test)]
 mod tests {
     use super::Config;
+    use crate::metadata::{Metadata, PageKind};
 
     const MINIMAL: &str = r"
 dist_path: ./dist
@@ -154,10 +440,110 @@ yamd_processors: {}
         assert_eq!(config.image_output_dir, "images");
     }
 
+    #[test]
+    fn negative_similarity_weight_is_rejected() {
+        let yaml = format!("{MINIMAL}similarity:\n  tags: 0.5\n  content: 2\n");
+        let config: Config = serde_yaml::from_str(&yaml).expect("parse");
+        assert!((config.similarity.content - 2.0).abs() < f64::EPSILON);
+
+        let yaml = format!("{MINIMAL}similarity:\n  content: -1\n");
+        let err = serde_yaml::from_str::<Config>(&yaml).unwrap_err();
+        assert!(
+            err.to_string().contains("weight must be 0 or greater"),
+            "got: {err}"
+        );
+    }
+
     #[test]
     fn image_output_dir_can_be_overridden() {
         let yaml = format!("{MINIMAL}image_output_dir: assets/img\n");
         let config: Config = serde_yaml::from_str(&yaml).expect("parse");
         assert_eq!(config.image_output_dir, "assets/img");
     }
+
+    #[test]
+    fn page_path_follows_pretty_urls() {
+        let config: Config = serde_yaml::from_str(MINIMAL).expect("parse");
+        assert!(config.pretty_urls.is_none());
+        assert_eq!(config.page_path("/posts/hello"), "/posts/hello.html");
+
+        let yaml = format!("{MINIMAL}pretty_urls:\n  redirects: true\n");
+        let config: Config = serde_yaml::from_str(&yaml).expect("parse");
+        assert!(config.pretty_urls.as_ref().is_some_and(|p| p.redirects));
+        assert_eq!(config.page_path("/posts/hello"), "/posts/hello/");
+    }
+
+    #[test]
+    fn section_index_path() {
+        let config: Config = serde_yaml::from_str(MINIMAL).expect("parse");
+        assert_eq!(config.section_template.as_ref(), "section.html");
+        assert_eq!(config.page_path("/travel/_index"), "/travel.html");
+        assert_eq!(config.page_path("/_index"), "/");
+        assert_eq!(config.section_path("/travel/europe"), "/travel/europe.html");
+    }
+
+    #[test]
+    fn permalink_uses_slug_and_pattern() {
+        let metadata = Metadata {
+            date: chrono::DateTime::parse_from_rfc3339("2024-03-05T10:00:00Z").unwrap(),
+            slug: Some("hello-world".into()),
+            ..Metadata::default()
+        };
+        let config: Config = serde_yaml::from_str(MINIMAL).expect("parse");
+        assert_eq!(
+            config.permalink("/posts/hello", &Metadata::default()),
+            "/posts/hello.html"
+        );
+        assert_eq!(
+            config.permalink("/posts/hello", &metadata),
+            "/posts/hello-world.html"
+        );
+
+        let yaml = format!("{MINIMAL}permalink: /{{section}}/{{year}}/{{month}}/{{slug}}\n");
+        let config: Config = serde_yaml::from_str(&yaml).expect("parse");
+        assert_eq!(
+            config.permalink("/posts/hello", &metadata),
+            "/posts/2024/03/hello-world.html"
+        );
+        assert_eq!(
+            config.permalink("/hello", &metadata),
+            "/2024/03/hello-world.html"
+        );
+        let page = Metadata {
+            kind: PageKind::Page,
+            ..metadata
+        };
+        assert_eq!(config.permalink("/about", &page), "/hello-world.html");
+        assert_eq!(config.permalink("/travel/_index", &page), "/travel.html");
+    }
+
+    #[test]
+    fn permalink_prefixes_other_languages() {
+        let yaml = format!("{MINIMAL}languages: [de]\n");
+        let config: Config = serde_yaml::from_str(&yaml).expect("parse");
+        assert_eq!(config.split_language("/post.de"), ("/post", Some("de")));
+        assert_eq!(config.split_language("/post.v2"), ("/post.v2", None));
+
+        let de = Metadata {
+            lang: Some("de".into()),
+            ..Metadata::default()
+        };
+        assert_eq!(
+            config.permalink("/posts/hello.de", &de),
+            "/de/posts/hello.html"
+        );
+        assert_eq!(
+            config.permalink("/travel/_index.de", &de),
+            "/de/travel.html"
+        );
+        assert_eq!(config.permalink("/_index.de", &de), "/de.html");
+        assert_eq!(
+            config.permalink("/posts/hello", &Metadata::default()),
+            "/posts/hello.html"
+        );
+
+        let yaml = format!("{MINIMAL}languages: [de]\npermalink: /{{slug}}/{{lang}}\n");
+        let config: Config = serde_yaml::from_str(&yaml).expect("parse");
+        assert_eq!(config.permalink("/posts/hello.de", &de), "/hello/de.html");
+    }
 }
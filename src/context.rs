@@ -2,7 +2,7 @@ use std::{path::PathBuf, sync::Arc};
 
 use syntect::parsing::SyntaxSet;
 
-use crate::{config::Config, pages::Pages, site::Site, theme::Theme};
+use crate::{config::Config, data::Data, i18n::I18n, pages::Pages, site::Site, theme::Theme};
 
 pub struct BuildConfig {
     pub path: PathBuf,
@@ -15,4 +15,6 @@ pub struct BuildContext {
     pub site: Arc<Site>,
     pub syntax_set: Arc<SyntaxSet>,
     pub theme: Arc<Theme>,
+    pub i18n: Arc<I18n>,
+    pub data: Arc<Data>,
 }
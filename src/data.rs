@@ -0,0 +1,383 @@
+use std::{collections::BTreeMap, ops::Range, path::Path, sync::Arc};
+
+use miette::SourceSpan;
+use serde_json::{Map, Value};
+
+use crate::{
+    diagnostic::BarDiagnostic,
+    fs::{get_files_by_ext_deep, read_to_string},
+    pages::url_slug,
+};
+
+pub const DATA_DIR: &str = "data";
+
+/// Supported extensions, in the order they are tried for paths without one.
+const EXTENSIONS: [&str; 5] = ["yaml", "yml", "toml", "json", "csv"];
+
+/// Structured files of the project's `data/` directory, keyed by path relative to it.
+#[derive(Debug, Default)]
+pub struct Data {
+    files: BTreeMap<Arc<str>, Value>,
+}
+
+impl Data {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every YAML, TOML, JSON and CSV file under `data/` in the project, in path order.
+    ///
+    /// # Errors
+    /// Returns an error if a file cannot be read or parsed.
+    pub async fn load(project_dir: &Path) -> Result<Self, BarDiagnostic> {
+        let mut data = Self::new();
+        let dir = project_dir.join(DATA_DIR);
+        if !dir.is_dir() {
+            return Ok(data);
+        }
+        for path in get_files_by_ext_deep(&dir, &EXTENSIONS).await? {
+            let name = path
+                .strip_prefix(&dir)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let content = read_to_string(&path).await?;
+            data.add_file(&name, &content)?;
+        }
+        Ok(data)
+    }
+
+    /// Parses `content` by the extension of `name` and stores it under `name`.
+    ///
+    /// # Errors
+    /// Returns an error labelled in `content` if it does not parse, or if the extension is
+    /// not supported.
+    pub fn add_file(&mut self, name: &str, content: &str) -> Result<(), BarDiagnostic> {
+        let extension = name.rsplit_once('.').map_or("", |(_, extension)| extension);
+        let parsed = match extension {
+            "yaml" | "yml" => serde_yaml::from_str(content).map_err(|e| {
+                let at = e.location().map_or(0, |location| location.index());
+                (at..at, message_without_location(&e.to_string()))
+            }),
+            "toml" => toml::from_str(content)
+                .map(toml_to_json)
+                .map_err(|e| (e.span().unwrap_or(0..0), e.message().to_string())),
+            "json" => serde_json::from_str(content).map_err(|e| {
+                let at = offset(content, e.line(), e.column());
+                (at..at, message_without_location(&e.to_string()))
+            }),
+            "csv" => parse_csv(content),
+            _ => {
+                return Err(BarDiagnostic::new(format!(
+                    "unsupported data file: {DATA_DIR}/{name}"
+                ))
+                .with_help(format!("use one of: {}", EXTENSIONS.join(", "))));
+            }
+        };
+        let value = parsed.map_err(|(span, message)| {
+            let mut diag = BarDiagnostic::new(format!("invalid data file: {DATA_DIR}/{name}"))
+                .with_source_code(format!("{DATA_DIR}/{name}"), content)
+                .with_label(SourceSpan::from(span), message);
+            if extension == "csv" {
+                diag = diag.with_help(
+                    "the first row names the columns, every other row needs a value for each",
+                );
+            }
+            diag
+        })?;
+
+        self.files.insert(Arc::from(name), value);
+        Ok(())
+    }
+
+    /// Data of the file at `path` relative to `data/`. The extension can be left out.
+    #[must_use]
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        self.resolve(path).map(|(_, value)| value)
+    }
+
+    /// Name relative to `data/` and data of the file at `path`, see [`Data::get`].
+    #[must_use]
+    pub fn resolve(&self, path: &str) -> Option<(&str, &Value)> {
+        let path = path.trim_start_matches('/');
+        self.files
+            .get_key_value(path)
+            .or_else(|| {
+                EXTENSIONS.iter().find_map(|extension| {
+                    self.files
+                        .get_key_value(format!("{path}.{extension}").as_str())
+                })
+            })
+            .map(|(name, value)| (name.as_ref(), value))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// Path of the `n`th record (1-based) of a data file: `{n}` in `pattern` is replaced with `n`
+/// and `{field}` with the slug of that field of `record`.
+///
+/// # Errors
+/// Returns a message if a placeholder names a field the record does not have, or a field
+/// that is not a string, number or boolean.
+pub fn record_path(pattern: &str, record: &Value, n: usize) -> Result<String, String> {
+    let mut path = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        path.push_str(&rest[..start]);
+        let name = &rest[start + 1..end];
+        let value = match record.get(name) {
+            _ if name == "n" => n.to_string(),
+            Some(Value::String(value)) => url_slug(value),
+            Some(value @ (Value::Number(_) | Value::Bool(_))) => value.to_string(),
+            Some(_) => return Err(format!("field '{name}' of record {n} is not a plain value")),
+            None => return Err(format!("record {n} has no field '{name}'")),
+        };
+        path.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    path.push_str(rest);
+    Ok(path)
+}
+
+/// Converts TOML to JSON, with dates and times as their TOML text, e.g. `2024-05-01`.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(integer) => integer.into(),
+        toml::Value::Float(float) => float.into(),
+        toml::Value::Boolean(boolean) => Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => array.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect::<Map<_, _>>()
+            .into(),
+    }
+}
+
+/// serde error message without the trailing `at line X column Y`, which the label shows.
+fn message_without_location(message: &str) -> String {
+    message
+        .split(" at line ")
+        .next()
+        .unwrap_or(message)
+        .to_string()
+}
+
+/// Byte offset of the 1-based `line` and `column`.
+fn offset(content: &str, line: usize, column: usize) -> usize {
+    let line_start = content
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+    (line_start + column.saturating_sub(1)).min(content.len())
+}
+
+/// Rows of comma separated values as objects keyed by the header row. Fields can be quoted
+/// with `"`, a quote inside is written as `""`. Values are strings, empty lines are skipped.
+fn parse_csv(content: &str) -> Result<Value, (Range<usize>, String)> {
+    let mut rows: Vec<(Range<usize>, Vec<String>)> = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut row_start = 0;
+    let mut quote_start = None;
+    let mut chars = content.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if quote_start.is_some() {
+            if c != '"' {
+                field.push(c);
+            } else if chars.next_if(|(_, next)| *next == '"').is_some() {
+                field.push('"');
+            } else {
+                quote_start = None;
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quote_start = Some(i),
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek().is_some_and(|(_, next)| *next == '\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_start..i, std::mem::take(&mut row)));
+                row_start = i + 1;
+            }
+            _ => field.push(c),
+        }
+    }
+    if let Some(start) = quote_start {
+        return Err((
+            start..content.len(),
+            "unterminated quoted field".to_string(),
+        ));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_start..content.len(), row));
+    }
+
+    let mut rows = rows
+        .into_iter()
+        .filter(|(_, row)| !matches!(row.as_slice(), [field] if field.trim().is_empty()));
+    let Some((_, header)) = rows.next() else {
+        return Ok(Value::Array(vec![]));
+    };
+    let header = header
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect::<Vec<_>>();
+    rows.map(|(span, row)| {
+        if row.len() != header.len() {
+            return Err((
+                span,
+                format!("expected {} fields, found {}", header.len(), row.len()),
+            ));
+        }
+        Ok(Value::Object(
+            header
+                .iter()
+                .cloned()
+                .zip(row.into_iter().map(Value::String))
+                .collect::<Map<_, _>>(),
+        ))
+    })
+    .collect::<Result<Vec<_>, _>>()
+    .map(Value::Array)
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::Diagnostic;
+    use serde_json::json;
+
+    use super::{Data, record_path};
+
+    fn label(err: &crate::diagnostic::BarDiagnostic) -> (usize, String) {
+        let label = err.labels().unwrap().next().unwrap();
+        (
+            label.offset(),
+            label.label().unwrap_or_default().to_string(),
+        )
+    }
+
+    #[test]
+    fn loads_every_format() {
+        let mut data = Data::new();
+        data.add_file("projects.yaml", "- name: bar\n  stars: 3\n")
+            .unwrap();
+        data.add_file("site/links.toml", "[[link]]\nurl = \"https://a.b\"\n")
+            .unwrap();
+        data.add_file("talks.json", r#"{"count": 2}"#).unwrap();
+        data.add_file("books.csv", "title,author\n\"Dune, part 1\",Herbert\n")
+            .unwrap();
+
+        assert_eq!(data.len(), 4);
+        assert_eq!(
+            data.get("projects.yaml"),
+            Some(&json!([{"name": "bar", "stars": 3}]))
+        );
+        assert_eq!(
+            data.get("/site/links"),
+            Some(&json!({"link": [{"url": "https://a.b"}]}))
+        );
+        assert_eq!(data.get("talks"), Some(&json!({"count": 2})));
+        assert_eq!(
+            data.get("books"),
+            Some(&json!([{"title": "Dune, part 1", "author": "Herbert"}]))
+        );
+        assert!(data.get("missing").is_none());
+    }
+
+    #[test]
+    fn toml_dates_are_strings() {
+        let mut data = Data::new();
+        data.add_file(
+            "talks.toml",
+            "[[talk]]\ndate = 2024-05-01\nat = 2024-05-01T10:00:00+02:00\nlength = 1.5\n",
+        )
+        .unwrap();
+        assert_eq!(
+            data.get("talks"),
+            Some(&json!({"talk": [{
+                "date": "2024-05-01",
+                "at": "2024-05-01T10:00:00+02:00",
+                "length": 1.5
+            }]}))
+        );
+    }
+
+    #[test]
+    fn csv_handles_quotes_and_line_endings() {
+        let mut data = Data::new();
+        data.add_file(
+            "quotes.csv",
+            "text,by\r\n\"He said \"\"hi\"\"\nthen left\",me\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            data.get("quotes.csv"),
+            Some(&json!([{"text": "He said \"hi\"\nthen left", "by": "me"}]))
+        );
+    }
+
+    #[test]
+    fn parse_errors_point_into_the_file() {
+        let mut data = Data::new();
+        let err = data
+            .add_file("talks.json", "{\n  \"count\": ,\n}")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "invalid data file: data/talks.json");
+        assert_eq!(label(&err).0, 13);
+
+        let err = data
+            .add_file("books.csv", "title,author\nDune\n")
+            .unwrap_err();
+        assert_eq!(label(&err), (13, "expected 2 fields, found 1".to_string()));
+        assert!(err.help().is_some());
+
+        let err = data.add_file("site.yaml", "a: [1\n").unwrap_err();
+        assert!(label(&err).0 > 0);
+
+        assert!(data.add_file("notes.txt", "").is_err());
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn record_path_expands_fields() {
+        let record = json!({"title": "Mont Blanc", "year": 2024, "tags": []});
+        assert_eq!(
+            record_path("/hikes/{year}/{title}-{n}.html", &record, 3).unwrap(),
+            "/hikes/2024/mont-blanc-3.html"
+        );
+        assert_eq!(
+            record_path("/hikes/{slug}.html", &record, 1).unwrap_err(),
+            "record 1 has no field 'slug'"
+        );
+        assert!(record_path("/hikes/{tags}.html", &record, 1).is_err());
+    }
+
+    #[test]
+    fn record_path_slugs_are_url_safe() {
+        let record = json!({"title": "Mt. Blanc!", "region": "Rhône-Alpes"});
+        assert_eq!(
+            record_path("/hikes/{region}/{title}.html", &record, 1).unwrap(),
+            "/hikes/rhone-alpes/mt-blanc.html"
+        );
+    }
+}
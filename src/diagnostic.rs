@@ -63,6 +63,11 @@ impl BarDiagnostic {
         self.help = Some(help.into());
         self
     }
+
+    #[must_use]
+    pub fn has_labels(&self) -> bool {
+        !self.labels.is_empty()
+    }
 }
 
 impl Display for BarDiagnostic {
@@ -104,9 +109,10 @@ impl Diagnostic for BarDiagnostic {
     }
 
     fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
-        self.help
-            .as_deref()
-            .map(|h| Box::new(h) as Box<dyn Display + 'a>)
+        if let Some(help) = self.help.as_deref() {
+            return Some(Box::new(help) as Box<dyn Display + 'a>);
+        }
+        self.source_error.as_deref().and_then(Diagnostic::help)
     }
 
     fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
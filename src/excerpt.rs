@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use yamd::op::{Node, Op, OpKind};
+
+use crate::pages::plain_text;
+
+/// Paragraph that ends the excerpt. It is not rendered.
+pub const EXCERPT_MARKER: &str = "<!-- more -->";
+
+pub const DEFAULT_EXCERPT_WORDS: usize = 50;
+
+/// Beginning of the page content, used when metadata has no `preview`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Excerpt {
+    pub text: String,
+    /// Op ranges of the top level nodes the excerpt is made of.
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl Excerpt {
+    /// Everything before the [`EXCERPT_MARKER`] paragraph or, without a marker, the leading
+    /// paragraphs until `words` words are collected. Nodes are never split, only the plain text
+    /// is cut to `words` words.
+    #[must_use]
+    pub fn new(ops: &[Op], source: &str, words: usize) -> Self {
+        let blocks = top_level_nodes(ops);
+
+        if let Some(marker) = blocks
+            .iter()
+            .position(|block| is_excerpt_marker(ops, source, block.start))
+        {
+            let ranges = blocks[..marker]
+                .iter()
+                .filter(|block| !matches!(ops[block.start].kind, OpKind::Start(Node::Metadata)))
+                .cloned()
+                .collect::<Vec<_>>();
+            let text = text_of(ops, source, &ranges).join(" ");
+            return Self { text, ranges };
+        }
+
+        let mut ranges = vec![];
+        let mut text: Vec<String> = vec![];
+        for block in blocks
+            .into_iter()
+            .filter(|block| matches!(ops[block.start].kind, OpKind::Start(Node::Paragraph)))
+        {
+            if text.len() >= words {
+                break;
+            }
+            text.extend(text_of(ops, source, std::slice::from_ref(&block)));
+            ranges.push(block);
+        }
+        if text.len() > words {
+            text.truncate(words);
+            if let Some(last) = text.last_mut() {
+                last.push('…');
+            }
+        }
+        Self {
+            text: text.join(" "),
+            ranges,
+        }
+    }
+}
+
+/// Whether the node starting at `start` is the [`EXCERPT_MARKER`] paragraph.
+#[must_use]
+pub fn is_excerpt_marker(ops: &[Op], source: &str, start: usize) -> bool {
+    match ops.get(start..start + 3) {
+        Some(
+            [
+                Op {
+                    kind: OpKind::Start(Node::Paragraph),
+                    ..
+                },
+                value @ Op {
+                    kind: OpKind::Value,
+                    ..
+                },
+                Op {
+                    kind: OpKind::End(Node::Paragraph),
+                    ..
+                },
+            ],
+        ) => value.content.as_str(source).trim() == EXCERPT_MARKER,
+        _ => false,
+    }
+}
+
+/// Op ranges of the nodes directly inside the document, including metadata.
+fn top_level_nodes(ops: &[Op]) -> Vec<Range<usize>> {
+    let mut nodes = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, op) in ops.iter().enumerate() {
+        match &op.kind {
+            OpKind::Start(Node::Document) | OpKind::End(Node::Document) | OpKind::Value => {}
+            OpKind::Start(_) => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            OpKind::End(_) => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    nodes.push(start..i + 1);
+                }
+            }
+        }
+    }
+    nodes
+}
+
+fn text_of(ops: &[Op], source: &str, ranges: &[Range<usize>]) -> Vec<String> {
+    ranges
+        .iter()
+        .flat_map(|range| {
+            let (prose, _) = plain_text(&ops[range.clone()], source);
+            prose
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use yamd::op;
+
+    use super::Excerpt;
+
+    const HEADER: &str = "---\ntitle: t\n---\n\n";
+
+    #[test]
+    fn excerpt_ends_at_marker() {
+        let source = format!("{HEADER}# Head\n\nFirst **para**.\n\n<!-- more -->\n\nSecond para.");
+        let ops = op::parse(&source);
+        let excerpt = Excerpt::new(&ops, &source, 1);
+        assert_eq!(excerpt.text, "Head First para.");
+        assert_eq!(excerpt.ranges.len(), 2);
+    }
+
+    #[test]
+    fn excerpt_takes_leading_paragraphs_up_to_word_limit() {
+        let source = format!("{HEADER}# Head\n\none two three\n\nfour five six\n\nseven");
+        let ops = op::parse(&source);
+
+        let excerpt = Excerpt::new(&ops, &source, 4);
+        assert_eq!(excerpt.text, "one two three four…");
+        assert_eq!(excerpt.ranges.len(), 2);
+
+        let excerpt = Excerpt::new(&ops, &source, 3);
+        assert_eq!(excerpt.text, "one two three");
+        assert_eq!(excerpt.ranges.len(), 1);
+    }
+
+    #[test]
+    fn excerpt_of_empty_page() {
+        let ops = op::parse(HEADER);
+        assert_eq!(Excerpt::new(&ops, HEADER, 10), Excerpt::default());
+    }
+}
@@ -3,7 +3,9 @@ use std::{path::PathBuf, sync::Arc};
 use syntect::parsing::SyntaxSet;
 use tera::Tera;
 
-use crate::{config::Config, pages::Pages, render::RenderedContentCache, site::Site};
+use crate::{
+    config::Config, data::Data, i18n::I18n, pages::Pages, render::RenderedContentCache, site::Site,
+};
 
 pub struct FragmentServices {
     pub site: Arc<Site>,
@@ -13,6 +15,8 @@ pub struct FragmentServices {
     pub syntax_set: Arc<SyntaxSet>,
     pub rendered_cache: RenderedContentCache,
     pub image_widths: Arc<Vec<usize>>,
+    pub i18n: Arc<I18n>,
+    pub data: Arc<Data>,
 }
 
 impl FragmentServices {
@@ -25,6 +29,8 @@ impl FragmentServices {
             &self.pages,
             self.rendered_cache.clone(),
             self.image_widths.clone(),
+            self.i18n.clone(),
+            self.data.clone(),
         );
     }
 }
@@ -32,7 +38,7 @@ impl FragmentServices {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{GpxEmbeddingConfig, YamdProcessors};
+    use crate::config::{GpxEmbeddingConfig, RedirectManifests, SimilarityConfig, YamdProcessors};
     use crate::site::Site;
     use std::path::PathBuf;
 
@@ -50,11 +56,23 @@ mod tests {
             title: Arc::from("test"),
             description: Arc::from("test"),
             language: Arc::from("en"),
+            languages: vec![],
             template_config: std::collections::HashMap::new(),
             yamd_processors: YamdProcessors {
                 generate_alt_text: None,
             },
             gpx_embedding: GpxEmbeddingConfig::default(),
+            pretty_urls: None,
+            redirect_manifests: RedirectManifests::default(),
+            front_matter_schema: std::collections::HashMap::new(),
+            authors: std::collections::HashMap::new(),
+            tags: std::collections::HashMap::new(),
+            permalink: None,
+            section_template: Arc::from("section.html"),
+            words_per_minute: 200,
+            excerpt_words: 50,
+            link_graph: None,
+            similarity: SimilarityConfig::default(),
         });
         let pages = Arc::new(Pages::new());
         let syntax_set = crate::syntax_highlight::init().unwrap();
@@ -67,6 +85,8 @@ mod tests {
             syntax_set,
             rendered_cache,
             image_widths: Arc::new(vec![352, 704, 1008, 1568, 2016, 3840]),
+            i18n: Arc::new(I18n::new("en")),
+            data: Arc::new(Data::new()),
         };
         let mut tera = tera::Tera::default();
         services.register(&mut tera);
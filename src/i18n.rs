@@ -0,0 +1,417 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::diagnostic::BarDiagnostic;
+
+pub const I18N_DIR: &str = "i18n";
+
+const PLURAL_CATEGORIES: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+/// A single translatable string, either plain text or a set of plural forms.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Plural(HashMap<String, String>),
+}
+
+impl Message {
+    fn select(&self, lang: &str, count: Option<i64>) -> &str {
+        match (self, count) {
+            (Message::Text(text), _) => text,
+            (Message::Plural(forms), count) => {
+                let count = count.unwrap_or(1);
+                let category = if count == 0 && forms.contains_key("zero") {
+                    "zero"
+                } else {
+                    plural_category(lang, count)
+                };
+                forms
+                    .get(category)
+                    .or_else(|| forms.get("other"))
+                    .map_or("", String::as_str)
+            }
+        }
+    }
+}
+
+/// Translation tables keyed by language, merged from the theme and the project.
+#[derive(Debug, Default)]
+pub struct I18n {
+    default_language: Arc<str>,
+    tables: HashMap<Arc<str>, HashMap<String, Message>>,
+    /// Language of the page being rendered, used by `t` without a `lang` arg.
+    page_language: Mutex<Option<Arc<str>>>,
+}
+
+impl I18n {
+    #[must_use]
+    pub fn new(default_language: &str) -> Self {
+        Self {
+            default_language: Arc::from(default_language),
+            tables: HashMap::new(),
+            page_language: Mutex::new(None),
+        }
+    }
+
+    /// Loads `i18n/<lang>.toml` tables from the theme, then applies the project's own
+    /// `i18n/` tables on top so individual strings can be overridden.
+    ///
+    /// # Errors
+    /// Returns an error if a table cannot be read or is not a valid translation table.
+    pub fn load(
+        theme_dir: &Path,
+        project_dir: &Path,
+        default_language: &str,
+    ) -> Result<Self, BarDiagnostic> {
+        let mut i18n = Self::new(default_language);
+        for dir in [theme_dir.join(I18N_DIR), project_dir.join(I18N_DIR)] {
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut files = std::fs::read_dir(&dir)
+                .map_err(|e| {
+                    BarDiagnostic::new(format!("failed to read directory: {}", dir.display()))
+                        .with_source(e.into())
+                })?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .collect::<Vec<_>>();
+            files.sort();
+            for path in files {
+                let Some(lang) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                let content = std::fs::read_to_string(&path).map_err(|e| {
+                    BarDiagnostic::new(format!("failed to read translations: {}", path.display()))
+                        .with_source(e.into())
+                })?;
+                i18n.add_table(lang, &path.display().to_string(), &content)?;
+            }
+        }
+        Ok(i18n)
+    }
+
+    /// Parses a TOML translation table and merges it into `lang`, overriding existing keys.
+    /// Nested tables become dotted keys; a table of plural categories (`one`, `other`, ...)
+    /// is a single plural message.
+    ///
+    /// # Errors
+    /// Returns an error if `content` is not valid TOML or contains non-string values.
+    pub fn add_table(
+        &mut self,
+        lang: &str,
+        name: &str,
+        content: &str,
+    ) -> Result<(), BarDiagnostic> {
+        let table: toml::Table = toml::from_str(content).map_err(|e| {
+            let mut diag = BarDiagnostic::new("invalid translation table")
+                .with_source_code(name.to_string(), content.to_string());
+            if let Some(span) = e.span() {
+                diag = diag.with_label(span.into(), e.message().to_string());
+            }
+            diag
+        })?;
+        let messages = self.tables.entry(Arc::from(lang)).or_default();
+        flatten(name, "", &table, messages)
+    }
+
+    /// Translates `key` into `lang`, trying the language, its base (`pt` for `pt-BR`),
+    /// and finally the theme's default language. `{count}` in the message is replaced
+    /// with `count`.
+    #[must_use]
+    pub fn translate(&self, key: &str, lang: &str, count: Option<i64>) -> Option<String> {
+        let base = lang.split_once('-').map(|(base, _)| base);
+        let (lang, message) = [Some(lang), base, Some(&*self.default_language)]
+            .into_iter()
+            .flatten()
+            .find_map(|lang| {
+                self.tables
+                    .get(lang)
+                    .and_then(|table| table.get(key))
+                    .map(|message| (lang, message))
+            })?;
+        let text = message.select(lang, count);
+        Some(match count {
+            Some(count) => text.replace("{count}", &count.to_string()),
+            None => text.to_string(),
+        })
+    }
+
+    #[must_use]
+    pub fn default_language(&self) -> &str {
+        &self.default_language
+    }
+
+    /// Language of the page being rendered, `None` for pages without one.
+    ///
+    /// # Panics
+    /// Panics if the page language mutex is poisoned.
+    #[must_use]
+    pub fn page_language(&self) -> Option<Arc<str>> {
+        self.page_language
+            .lock()
+            .expect("page language mutex poisoned")
+            .clone()
+    }
+
+    /// Set before rendering each page, so its strings default to its language.
+    ///
+    /// # Panics
+    /// Panics if the page language mutex is poisoned.
+    pub fn set_page_language(&self, lang: Option<Arc<str>>) {
+        *self
+            .page_language
+            .lock()
+            .expect("page language mutex poisoned") = lang;
+    }
+
+    /// Lists problems worth warning about: site `languages` without a table, and keys
+    /// of the default language that other tables do not translate.
+    #[must_use]
+    pub fn check(&self, languages: &[Arc<str>]) -> Vec<String> {
+        let mut warnings = vec![];
+        if self.tables.is_empty() {
+            return warnings;
+        }
+        let Some(reference) = self.tables.get(&self.default_language) else {
+            warnings.push(format!(
+                "no translations for the default language '{}'",
+                self.default_language
+            ));
+            return warnings;
+        };
+        for lang in languages {
+            let base = lang.split_once('-').map_or(&**lang, |(base, _)| base);
+            if !self.tables.contains_key(lang) && !self.tables.contains_key(base) {
+                warnings.push(format!("no translations for language '{lang}'"));
+            }
+        }
+        let langs = self.tables.keys().collect::<BTreeSet<_>>();
+        for lang in langs {
+            if *lang == self.default_language {
+                continue;
+            }
+            let table = &self.tables[lang];
+            let missing = reference
+                .keys()
+                .filter(|key| !table.contains_key(*key))
+                .collect::<BTreeSet<_>>();
+            for key in missing {
+                warnings.push(format!("missing translation for '{key}' in '{lang}'"));
+            }
+        }
+        warnings
+    }
+}
+
+fn flatten(
+    name: &str,
+    prefix: &str,
+    table: &toml::Table,
+    messages: &mut HashMap<String, Message>,
+) -> Result<(), BarDiagnostic> {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            toml::Value::String(text) => {
+                messages.insert(key, Message::Text(text.clone()));
+            }
+            toml::Value::Table(inner) if is_plural(inner) => {
+                let mut forms = HashMap::new();
+                for (category, form) in inner {
+                    let toml::Value::String(form) = form else {
+                        return Err(BarDiagnostic::new(format!(
+                            "{name}: plural form '{key}.{category}' must be a string"
+                        )));
+                    };
+                    forms.insert(category.clone(), form.clone());
+                }
+                if !forms.contains_key("other") {
+                    return Err(BarDiagnostic::new(format!(
+                        "{name}: plural forms of '{key}' have no 'other' form"
+                    ))
+                    .with_help("add an `other` entry, it is used when no other form matches"));
+                }
+                messages.insert(key, Message::Plural(forms));
+            }
+            toml::Value::Table(inner) => flatten(name, &key, inner, messages)?,
+            _ => {
+                return Err(BarDiagnostic::new(format!(
+                    "{name}: translation '{key}' must be a string or a table"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_plural(table: &toml::Table) -> bool {
+    !table.is_empty()
+        && table
+            .keys()
+            .all(|key| PLURAL_CATEGORIES.contains(&key.as_str()))
+}
+
+/// The CLDR plural category of an integer `n` for the common plural rule families.
+fn plural_category(lang: &str, n: i64) -> &'static str {
+    let base = lang.split_once('-').map_or(lang, |(base, _)| base);
+    let n = n.unsigned_abs();
+    let (n10, n100) = (n % 10, n % 100);
+    match base {
+        "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" => "other",
+        "fr" | "pt" => {
+            if n <= 1 {
+                "one"
+            } else {
+                "other"
+            }
+        }
+        "ru" | "uk" | "be" | "sr" | "hr" | "bs" => {
+            if n10 == 1 && n100 != 11 {
+                "one"
+            } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                "few"
+            } else {
+                "many"
+            }
+        }
+        "pl" => {
+            if n == 1 {
+                "one"
+            } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                "few"
+            } else {
+                "many"
+            }
+        }
+        "cs" | "sk" => match n {
+            1 => "one",
+            2..=4 => "few",
+            _ => "other",
+        },
+        _ => {
+            if n == 1 {
+                "one"
+            } else {
+                "other"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = r#"
+read_more = "Read more"
+
+[tags]
+title = "Tags"
+
+[posts]
+one = "{count} post"
+other = "{count} posts"
+"#;
+
+    const RU: &str = r#"
+read_more = "Читать далее"
+
+[posts]
+one = "{count} запись"
+few = "{count} записи"
+many = "{count} записей"
+other = "{count} записи"
+"#;
+
+    fn i18n() -> I18n {
+        let mut i18n = I18n::new("en");
+        i18n.add_table("en", "en.toml", EN).unwrap();
+        i18n.add_table("ru", "ru.toml", RU).unwrap();
+        i18n
+    }
+
+    #[test]
+    fn translate_flattens_nested_tables() {
+        let i18n = i18n();
+        assert_eq!(i18n.translate("tags.title", "en", None).unwrap(), "Tags");
+        assert_eq!(
+            i18n.translate("read_more", "ru", None).unwrap(),
+            "Читать далее"
+        );
+    }
+
+    #[test]
+    fn translate_falls_back_to_default_language() {
+        let i18n = i18n();
+        assert_eq!(i18n.translate("tags.title", "ru", None).unwrap(), "Tags");
+        assert_eq!(
+            i18n.translate("read_more", "de", None).unwrap(),
+            "Read more"
+        );
+        assert!(i18n.translate("unknown", "en", None).is_none());
+    }
+
+    #[test]
+    fn translate_uses_base_language() {
+        let i18n = i18n();
+        assert_eq!(
+            i18n.translate("read_more", "ru-RU", None).unwrap(),
+            "Читать далее"
+        );
+    }
+
+    #[test]
+    fn translate_selects_plural_forms() {
+        let i18n = i18n();
+        assert_eq!(i18n.translate("posts", "en", Some(1)).unwrap(), "1 post");
+        assert_eq!(i18n.translate("posts", "en", Some(0)).unwrap(), "0 posts");
+        assert_eq!(
+            i18n.translate("posts", "ru", Some(21)).unwrap(),
+            "21 запись"
+        );
+        assert_eq!(i18n.translate("posts", "ru", Some(3)).unwrap(), "3 записи");
+        assert_eq!(
+            i18n.translate("posts", "ru", Some(12)).unwrap(),
+            "12 записей"
+        );
+    }
+
+    #[test]
+    fn project_tables_override_theme_strings() {
+        let mut i18n = i18n();
+        i18n.add_table("en", "project/en.toml", r#"read_more = "Continue""#)
+            .unwrap();
+        assert_eq!(i18n.translate("read_more", "en", None).unwrap(), "Continue");
+        assert_eq!(i18n.translate("tags.title", "en", None).unwrap(), "Tags");
+    }
+
+    #[test]
+    fn plural_without_other_is_an_error() {
+        let mut i18n = I18n::new("en");
+        let err = i18n
+            .add_table("en", "en.toml", "[posts]\none = \"post\"")
+            .unwrap_err();
+        assert!(err.to_string().contains("'other'"), "got: {err}");
+    }
+
+    #[test]
+    fn check_reports_missing_keys_and_languages() {
+        let i18n = i18n();
+        let warnings = i18n.check(&[Arc::from("en"), Arc::from("ru"), Arc::from("fr")]);
+        assert_eq!(
+            warnings,
+            vec![
+                "no translations for language 'fr'".to_string(),
+                "missing translation for 'tags.title' in 'ru'".to_string(),
+            ]
+        );
+    }
+}
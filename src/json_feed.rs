@@ -1,31 +1,75 @@
 use std::{
+    collections::HashMap,
     fmt::{Display, Formatter},
     sync::Arc,
 };
 
 use cloudinary::transformation::{Image, Transformations, crop_mode::CropMode, gravity::Gravity};
-use rss::{Category, Guid, Item, ItemBuilder};
+use rss::{Category, Guid, Item, ItemBuilder, extension::dublincore::DublinCoreExtensionBuilder};
 use serde::Serialize;
 use url::Url;
 
-use crate::pages::Page;
+use crate::{
+    config::Author,
+    pages::{ContentStats, Page, Pages},
+};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeedAuthor {
+    name: Arc<str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avatar: Option<Url>,
+    #[serde(skip)]
+    email: Option<Arc<str>>,
+}
+
+impl FeedAuthor {
+    #[must_use]
+    pub fn new(author: &Author, base_url: &Url) -> Self {
+        Self {
+            name: author.name.clone(),
+            url: author.url.clone(),
+            avatar: author
+                .avatar
+                .as_ref()
+                .and_then(|avatar| base_url.join(avatar).ok()),
+            email: author.email.clone(),
+        }
+    }
+}
 
 #[derive(Serialize, Debug, Clone)]
 pub struct FeedItem {
     id: Arc<str>,
     title: Arc<str>,
     content_text: Arc<str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_html: Option<Arc<str>>,
     url: Url,
     image: Option<Url>,
     pub date_published: Arc<str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<Arc<str>>,
     tags: Vec<Arc<str>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authors: Vec<FeedAuthor>,
+    /// JSON Feed extension with word count and reading time.
+    #[serde(rename = "_stats")]
+    pub stats: ContentStats,
 }
 
 impl FeedItem {
     #[must_use]
-    pub fn new(page: &Page, base_url: &Url) -> Self {
+    pub fn new(
+        page: &Page,
+        base_url: &Url,
+        authors: &HashMap<Arc<str>, Author>,
+        pages: &Pages,
+    ) -> Self {
         let mut url = base_url.clone();
-        url.set_path(format!("{}.html", page.pid).as_str());
+        url.set_path(&page.path);
         let image =
             page.get_image(base_url)
                 .map(|src| match Image::try_from(src.clone()) {
@@ -46,14 +90,37 @@ impl FeedItem {
             id: page.pid.clone(),
             title: page.get_title().into(),
             image,
-            content_text: page.metadata.preview.clone().unwrap_or_default().into(),
+            content_text: page
+                .metadata
+                .preview
+                .clone()
+                .unwrap_or_else(|| page.excerpt.clone())
+                .into(),
+            content_html: None,
 
             url,
             date_published: page.metadata.date.format("%+").to_string().into(),
-            tags: page.metadata.tags.clone().unwrap_or_default(),
+            language: page.metadata.lang.clone(),
+            tags: page.tags.iter().map(|tag| pages.tag_name(tag)).collect(),
+            authors: page
+                .metadata
+                .authors
+                .iter()
+                .flatten()
+                .filter_map(|id| authors.get(id))
+                .map(|author| FeedAuthor::new(author, base_url))
+                .collect(),
+            stats: page.stats,
         }
     }
 
+    /// Sets the HTML variant of the item content, empty HTML is left out.
+    #[must_use]
+    pub fn with_content_html(mut self, html: Option<String>) -> Self {
+        self.content_html = html.filter(|html| !html.trim().is_empty()).map(Arc::from);
+        self
+    }
+
     #[must_use]
     pub fn to_rss_item(&self) -> Item {
         ItemBuilder::default()
@@ -74,6 +141,26 @@ impl FeedItem {
                     })
                     .collect::<Vec<Category>>(),
             )
+            .author(self.authors.iter().find_map(|author| {
+                author
+                    .email
+                    .as_ref()
+                    .map(|email| format!("{email} ({})", author.name))
+            }))
+            .dublin_core_ext(if self.authors.is_empty() {
+                None
+            } else {
+                Some(
+                    DublinCoreExtensionBuilder::default()
+                        .creators(
+                            self.authors
+                                .iter()
+                                .map(|author| author.name.to_string())
+                                .collect::<Vec<String>>(),
+                        )
+                        .build(),
+                )
+            })
             .build()
     }
 }
@@ -145,3 +232,109 @@ impl Display for JsonFeed {
         write!(f, "{}", serde_json::to_string(self).unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use url::Url;
+
+    use crate::{
+        config::{Author, Tag},
+        metadata::Metadata,
+        pages::{Page, Pages},
+    };
+
+    use super::FeedItem;
+
+    #[test]
+    fn feed_item_uses_tag_display_names() {
+        let base_url = Url::parse("https://example.com").unwrap();
+        let mut registry = HashMap::new();
+        registry.insert(
+            "rust".into(),
+            Tag {
+                name: Some("Rust".into()),
+                aliases: vec!["rustlang".into()],
+                ..Tag::default()
+            },
+        );
+        let mut pages = Pages::new().with_tag_registry(&registry);
+        pages.push(Page::new(
+            "/post".into(),
+            vec![],
+            String::new(),
+            Metadata {
+                tags: Some(vec!["RustLang".into(), "Web Dev".into()]),
+                ..Metadata::default()
+            },
+        ));
+
+        let page = pages.get("/post").unwrap();
+        let item = FeedItem::new(page, &base_url, &HashMap::new(), &pages);
+        let names: Vec<String> = item
+            .to_rss_item()
+            .categories()
+            .iter()
+            .map(|category| category.name.clone())
+            .collect();
+        assert_eq!(names, vec!["Rust", "Web Dev"]);
+    }
+
+    #[test]
+    fn feed_item_resolves_authors() {
+        let base_url = Url::parse("https://example.com").unwrap();
+        let mut authors = HashMap::new();
+        authors.insert(
+            "jane".into(),
+            Author {
+                name: "Jane".into(),
+                url: None,
+                avatar: Some("/jane.png".into()),
+                bio: None,
+                email: Some("jane@example.com".into()),
+            },
+        );
+        let page = Page::new(
+            "/post".into(),
+            vec![],
+            String::new(),
+            Metadata {
+                authors: Some(vec!["jane".into()]),
+                ..Metadata::default()
+            },
+        );
+
+        let item = FeedItem::new(&page, &base_url, &authors, &Pages::new());
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["authors"][0]["name"], "Jane");
+        assert_eq!(json["authors"][0]["avatar"], "https://example.com/jane.png");
+        assert!(json["authors"][0].get("email").is_none());
+
+        let rss = item.to_rss_item();
+        assert_eq!(rss.author(), Some("jane@example.com (Jane)"));
+        assert_eq!(
+            rss.dublin_core_ext()
+                .map(rss::extension::dublincore::DublinCoreExtension::creators),
+            Some(["Jane".to_string()].as_slice())
+        );
+    }
+
+    #[test]
+    fn feed_item_falls_back_to_excerpt() {
+        let base_url = Url::parse("https://example.com").unwrap();
+        let source = "---\ntitle: t\n---\n\nFirst words.\n\n<!-- more -->\n\nRest.";
+        let page = Page::new(
+            "/post".into(),
+            yamd::op::parse(source),
+            source.to_string(),
+            Metadata::default(),
+        );
+
+        let item = FeedItem::new(&page, &base_url, &HashMap::new(), &Pages::new())
+            .with_content_html(Some("<p>First words.</p>".to_string()));
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["content_text"], "First words.");
+        assert_eq!(json["content_html"], "<p>First words.</p>");
+    }
+}
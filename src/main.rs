@@ -4,12 +4,15 @@ mod cache;
 mod cloudinary;
 pub mod config;
 pub mod context;
+pub mod data;
 pub mod diagnostic;
+pub mod excerpt;
 pub mod fragment_services;
 pub mod fs;
 mod gallery;
 mod gallery_ops;
 mod gpx_embed;
+pub mod i18n;
 mod image_alt;
 pub mod json_feed;
 mod metadata;
@@ -17,20 +20,26 @@ pub mod pages;
 pub mod render;
 pub mod renderer;
 mod req;
+pub mod similarity;
 pub mod site;
 pub mod syntax_highlight;
 pub mod templating;
 pub mod theme;
 
-use args::{Args, ArticleArgs, BuildArgs, Commands};
+use args::{Args, ArticleArgs, BuildArgs, Commands, ThemeCommands};
 use clap::Parser;
 use config::Config;
 use context::{BuildConfig, BuildContext};
+use data::Data;
 use diagnostic::BarDiagnostic;
 use fs::write_file;
+use i18n::I18n;
 use metadata::Metadata;
 use renderer::render;
-use site::init_site;
+use site::{
+    add_alias_redirects, add_content_pages, add_link_graph, add_pretty_url_redirects,
+    add_section_pages, init_site,
+};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -38,7 +47,7 @@ use templating::initialize;
 use theme::Theme;
 use tokio::fs::{create_dir_all, remove_dir_all, remove_file, try_exists};
 use tokio::try_join;
-use tracing::subscriber;
+use tracing::{info, subscriber};
 use tracing_log::AsTrace;
 use tracing_subscriber::FmtSubscriber;
 use yamd::Yamd;
@@ -63,6 +72,9 @@ async fn main() {
             Some(Commands::Build(build_args)) => build(build_args).await,
             Some(Commands::Article(article_args)) => create_article(article_args).await,
             Some(Commands::Clear(clear_rgs)) => clear(clear_rgs).await,
+            Some(Commands::Theme(theme_args)) => match theme_args.command {
+                ThemeCommands::Check(check_args) => theme_check(check_args).await,
+            },
             None => {
                 build(BuildArgs {
                     path: PathBuf::from_str("./").expect("current directory path is valid"),
@@ -89,9 +101,34 @@ async fn build(args: BuildArgs) -> Result<(), BarDiagnostic> {
 
     let theme = Theme::load(&template_path.join("theme.toml"))?;
     theme.validate(env!("CARGO_PKG_VERSION"), &template_path)?;
+    let i18n = I18n::load(
+        &template_path,
+        &build_config.path,
+        &theme.theme.default_language,
+    )?;
     let syntax_set = init()?;
 
-    let (pages, site) = try_join!(init_pages(&build_config), init_site(&build_config))?;
+    let (pages, site, data) = try_join!(
+        init_pages(&build_config),
+        init_site(&build_config),
+        Data::load(&build_config.path)
+    )?;
+    if !data.is_empty() {
+        info!("loaded {} data files", data.len());
+    }
+
+    add_content_pages(&site, &pages);
+    add_section_pages(&site, &pages, &build_config.config, &template_path)?;
+    add_link_graph(&site, &pages, &build_config.config)?;
+
+    if build_config
+        .config
+        .pretty_urls
+        .as_ref()
+        .is_some_and(|pretty_urls| pretty_urls.redirects)
+    {
+        add_pretty_url_redirects(&site, &pages, &build_config.config)?;
+    }
 
     let theme = Arc::new(theme);
 
@@ -101,6 +138,8 @@ async fn build(args: BuildArgs) -> Result<(), BarDiagnostic> {
         site,
         syntax_set,
         theme,
+        i18n: Arc::new(i18n),
+        data: Arc::new(data),
     });
 
     let rendered_cache =
@@ -110,11 +149,37 @@ async fn build(args: BuildArgs) -> Result<(), BarDiagnostic> {
     let ctx_clone = ctx.clone();
     let cache = rendered_cache;
     tokio::task::spawn_blocking(move || render(&ctx_clone, &tera, &cache)).await??;
+    add_alias_redirects(&ctx.site, &ctx.pages, &ctx.config.config)?;
 
     ctx.site.save().await?;
     Ok(())
 }
 
+async fn theme_check(args: BuildArgs) -> Result<(), BarDiagnostic> {
+    let config = Config::try_from(&args.path)?;
+    let template_path = canonicalize_with_context(&args.path.join(&config.template)).await?;
+
+    let theme = Theme::load(&template_path.join("theme.toml"))?;
+    theme.validate(env!("CARGO_PKG_VERSION"), &template_path)?;
+    let i18n = I18n::load(&template_path, &args.path, &theme.theme.default_language)?;
+
+    let languages = std::iter::once(config.language.clone())
+        .chain(config.languages.iter().cloned())
+        .collect::<Vec<_>>();
+    let warnings = i18n.check(&languages);
+    for warning in &warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    println!(
+        "Theme '{}' checked with {} warning(s)",
+        theme.theme.name,
+        warnings.len()
+    );
+
+    Ok(())
+}
+
 async fn create_article(args: ArticleArgs) -> Result<(), BarDiagnostic> {
     let path = PathBuf::from(format!("./{}.yamd", args.title));
 
@@ -139,6 +204,7 @@ async fn create_article(args: ArticleArgs) -> Result<(), BarDiagnostic> {
         preview: Some(String::new()),
         tags: Some(vec![]),
         is_draft: Some(true),
+        ..Metadata::default()
     };
 
     let article = Yamd::new(
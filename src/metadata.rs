@@ -1,8 +1,28 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    sync::Arc,
+};
 
 use chrono::{DateTime, FixedOffset};
+use miette::SourceSpan;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    config::{Author, FieldType},
+    diagnostic::BarDiagnostic,
+};
+
+#[derive(Debug, PartialEq, Serialize, Default, Clone, Copy, Deserialize, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PageKind {
+    /// Dated content, listed in feeds, tags and similar pages.
+    #[default]
+    Article,
+    /// Standalone page like "about", left out of feeds, tags and similar pages.
+    Page,
+}
+
 #[derive(Debug, PartialEq, Serialize, Default, Clone, Deserialize, Eq)]
 pub struct Metadata {
     pub title: String,
@@ -13,6 +33,360 @@ pub struct Metadata {
     pub preview: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<Arc<str>>>,
+    /// Language of the page. Defaults to the file name suffix (`post.de.yamd`) or
+    /// `config.language`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<Arc<str>>,
+    /// Pages with the same key are translations of each other. Defaults to the file path
+    /// without language suffix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation_key: Option<Arc<str>>,
+    /// Last URL segment of the page, defaults to the file name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug: Option<Arc<str>>,
+    /// Path-style categories, e.g. `travel/europe/alps`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<Arc<str>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_draft: Option<bool>,
+    /// Old paths of the page. Each one is redirected to the page's current URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<Vec<Arc<str>>>,
+    /// Template the page is rendered with. BAR registers the page itself when it is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<Arc<str>>,
+    #[serde(default)]
+    pub kind: PageKind,
+    /// Ids of the authors in `config.authors`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authors: Option<Vec<Arc<str>>>,
+    /// Name of the series the page belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<Arc<str>>,
+    /// Position of the page in its series.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_order: Option<usize>,
+    /// Any other metadata key, passed to templates as is.
+    #[serde(flatten)]
+    pub extra: BTreeMap<Arc<str>, serde_yaml::Value>,
+}
+
+impl Metadata {
+    #[must_use]
+    pub fn is_article(&self) -> bool {
+        self.kind == PageKind::Article
+    }
+
+    /// Extra fields whose value does not match their type in `schema`. Fields missing from
+    /// either side are not checked.
+    #[must_use]
+    pub fn check_schema(&self, schema: &HashMap<Arc<str>, FieldType>) -> Vec<FieldError> {
+        self.extra
+            .iter()
+            .filter_map(|(key, value)| {
+                let typ = schema.get(key).filter(|typ| !typ.matches(value))?;
+                Some(FieldError {
+                    key: key.clone(),
+                    item: None,
+                    message: format!("must be {typ}"),
+                })
+            })
+            .collect()
+    }
+
+    /// Author ids missing from `authors`.
+    #[must_use]
+    pub fn check_authors(&self, authors: &HashMap<Arc<str>, Author>) -> Vec<FieldError> {
+        self.authors
+            .iter()
+            .flatten()
+            .filter(|id| !authors.contains_key(*id))
+            .map(|id| FieldError {
+                key: "authors".into(),
+                item: Some(id.clone()),
+                message: format!("unknown author '{id}'"),
+            })
+            .collect()
+    }
+}
+
+/// Problem with a field of parsed metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub key: Arc<str>,
+    /// Item of a list field the problem is about.
+    pub item: Option<Arc<str>>,
+    pub message: String,
+}
+
+const DATE_HELP: &str =
+    "dates are RFC 3339 with time and UTC offset, e.g. `date: 2024-01-31T10:00:00+02:00`";
+
+/// Metadata block of a content file, used to point errors at the file.
+pub struct MetadataSource<'a> {
+    pub name: &'a str,
+    /// Whole content file.
+    pub source: &'a str,
+    /// Byte range of the metadata text in `source`.
+    pub range: Range<usize>,
+}
+
+impl MetadataSource<'_> {
+    /// Error for a content file without a metadata block.
+    #[must_use]
+    pub fn missing(name: &str, source: &str) -> BarDiagnostic {
+        BarDiagnostic::new(format!("{name} is missing metadata"))
+            .with_source_code(name, source)
+            .with_label((0, 0).into(), "metadata block expected here")
+            .with_help("start the file with a block of `title` and `date` between `---` lines")
+    }
+
+    /// Error for metadata that does not parse, labelled at the location serde reports.
+    ///
+    /// The field in error is the top level key of the line at that location, so the label
+    /// is serde's message without the key and location it starts and ends with.
+    #[must_use]
+    pub fn yaml_error(&self, err: &serde_yaml::Error) -> BarDiagnostic {
+        let mut message = err.to_string();
+        let at = err.location().map_or(0, |location| {
+            let suffix = format!(" at line {} column {}", location.line(), location.column());
+            message = message.replacen(&suffix, "", 1);
+            location.index().min(self.range.len())
+        });
+        let key = self.key_at(at);
+        let label = key
+            .and_then(|key| message.strip_prefix(&format!("{key}: ")))
+            .unwrap_or(&message);
+        let mut diag = self.diagnostic().with_label(self.line_span(at), label);
+        if let Some(help) = yaml_help(key, label) {
+            diag = diag.with_help(help);
+        }
+        diag
+    }
+
+    /// Error labelling every field in `errors`.
+    #[must_use]
+    pub fn field_errors(&self, errors: &[FieldError]) -> BarDiagnostic {
+        errors.iter().fold(self.diagnostic(), |diag, error| {
+            let at = self.find(&error.key, error.item.as_deref());
+            let message = match &error.item {
+                Some(_) => error.message.clone(),
+                None => format!("'{}' {}", error.key, error.message),
+            };
+            diag.with_label(self.line_span(at), message)
+        })
+    }
+
+    fn diagnostic(&self) -> BarDiagnostic {
+        BarDiagnostic::new(format!("{} has invalid metadata", self.name))
+            .with_source_code(self.name, self.source)
+    }
+
+    fn text(&self) -> &str {
+        &self.source[self.range.clone()]
+    }
+
+    /// Offset in the metadata text of the top level `key`, or of `item` after it.
+    fn find(&self, key: &str, item: Option<&str>) -> usize {
+        let text = self.text();
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            if line
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+            {
+                return item
+                    .and_then(|item| text[offset..].find(item))
+                    .map_or(offset, |found| offset + found);
+            }
+            offset += line.len();
+        }
+        0
+    }
+
+    /// Top level key whose value contains `at` in the metadata text.
+    fn key_at(&self, at: usize) -> Option<&str> {
+        let text = self.text();
+        let end = text
+            .get(at..)
+            .and_then(|rest| rest.find('\n'))
+            .map_or(text.len(), |len| at + len);
+        text[..end]
+            .lines()
+            .rev()
+            .find(|line| !line.starts_with([' ', '\t', '-', '#']) && !line.trim().is_empty())
+            .and_then(|line| line.split_once(':'))
+            .map(|(key, _)| key.trim())
+    }
+
+    /// Span in `source` from `at` in the metadata text to the end of its line.
+    fn line_span(&self, at: usize) -> SourceSpan {
+        let rest = &self.text()[at..];
+        let len = rest.find('\n').unwrap_or(rest.len());
+        (self.range.start + at, rest[..len].trim_end().len()).into()
+    }
+}
+
+fn yaml_help(key: Option<&str>, message: &str) -> Option<String> {
+    if let Some(field) = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.strip_suffix('`'))
+    {
+        return Some(if field == "date" {
+            format!("add the page date, {DATE_HELP}")
+        } else {
+            format!("add `{field}` to the metadata block")
+        });
+    }
+    match key {
+        Some("date") => Some(DATE_HELP.to_string()),
+        Some(key) if message.contains("expected a sequence") => Some(format!(
+            "`{key}` is a list, write `{key}: [first, second]` or one `- item` per line"
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use miette::Diagnostic;
+
+    use crate::config::FieldType;
+
+    use super::{FieldError, Metadata, MetadataSource};
+
+    const YAML: &str = "title: t
+date: 2024-01-01T00:00:00+00:00
+subtitle: s
+rating: 5
+";
+
+    #[test]
+    fn unknown_keys_are_kept_in_extra() {
+        let metadata: Metadata = serde_yaml::from_str(YAML).expect("parse");
+        assert_eq!(metadata.title, "t");
+        assert_eq!(metadata.extra.len(), 2);
+        assert_eq!(
+            metadata.extra.get("subtitle").and_then(|v| v.as_str()),
+            Some("s")
+        );
+
+        let json = serde_json::to_value(&metadata).expect("serialize");
+        assert_eq!(json["subtitle"], "s");
+        assert_eq!(json["rating"], 5);
+    }
+
+    #[test]
+    fn check_schema_reports_mismatched_type() {
+        let metadata: Metadata = serde_yaml::from_str(YAML).expect("parse");
+        let mut schema: HashMap<Arc<str>, FieldType> = HashMap::new();
+        schema.insert("rating".into(), FieldType::Integer);
+        assert!(metadata.check_schema(&schema).is_empty());
+
+        schema.insert("subtitle".into(), FieldType::Date);
+        let errors = metadata.check_schema(&schema);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].key.as_ref(), "subtitle");
+        assert_eq!(errors[0].message, "must be date");
+    }
+
+    fn block(source: &str) -> MetadataSource<'_> {
+        let start = source.find('\n').unwrap() + 1;
+        let end = source[start..].find("---").unwrap() + start;
+        MetadataSource {
+            name: "post.yamd",
+            source,
+            range: start..end,
+        }
+    }
+
+    fn labels(diag: &crate::diagnostic::BarDiagnostic) -> Vec<(usize, usize, String)> {
+        diag.labels()
+            .into_iter()
+            .flatten()
+            .map(|label| {
+                (
+                    label.offset(),
+                    label.len(),
+                    label.label().unwrap_or_default().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn yaml_error_points_at_value_with_date_help() {
+        let source = "---\ntitle: t\ndate: 2024-01-01\n---\n\nbody";
+        let block = block(source);
+        let err = serde_yaml::from_str::<Metadata>(&source[block.range.clone()]).unwrap_err();
+        let diag = block.yaml_error(&err);
+
+        assert_eq!(diag.to_string(), "post.yamd has invalid metadata");
+        let labels = labels(&diag);
+        assert_eq!(labels.len(), 1);
+        assert_eq!(
+            &source[labels[0].0..labels[0].0 + labels[0].1],
+            "2024-01-01"
+        );
+        assert_eq!(labels[0].2, "premature end of input");
+        assert!(diag.help().unwrap().to_string().contains("RFC 3339"));
+    }
+
+    #[test]
+    fn yaml_error_suggests_list_for_string_tags() {
+        let source = "---\ntitle: t\ndate: 2024-01-01T00:00:00+00:00\ntags: rust\n---\n";
+        let block = block(source);
+        let err = serde_yaml::from_str::<Metadata>(&source[block.range.clone()]).unwrap_err();
+        let diag = block.yaml_error(&err);
+
+        let labels = labels(&diag);
+        assert_eq!(&source[labels[0].0..labels[0].0 + labels[0].1], "rust");
+        assert!(
+            diag.help()
+                .unwrap()
+                .to_string()
+                .contains("`tags: [first, second]`")
+        );
+    }
+
+    #[test]
+    fn field_errors_label_every_field() {
+        let source = "---\ntitle: t\nsubtitle: s\nauthors:\n  - jane\n  - bob\n---\n";
+        let diag = block(source).field_errors(&[
+            FieldError {
+                key: "subtitle".into(),
+                item: None,
+                message: "must be date".to_string(),
+            },
+            FieldError {
+                key: "authors".into(),
+                item: Some("bob".into()),
+                message: "unknown author 'bob'".to_string(),
+            },
+        ]);
+
+        let labels = labels(&diag)
+            .into_iter()
+            .map(|(offset, len, label)| (source[offset..offset + len].to_string(), label))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![
+                (
+                    "subtitle: s".to_string(),
+                    "'subtitle' must be date".to_string()
+                ),
+                ("bob".to_string(), "unknown author 'bob'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_metadata_has_help() {
+        let diag = MetadataSource::missing("post.yamd", "# Title");
+        assert_eq!(diag.to_string(), "post.yamd is missing metadata");
+        assert!(diag.help().is_some());
+    }
 }
@@ -1,24 +1,29 @@
 use crate::{
     r#async::try_map,
     cloudinary::unwrap_cloudinary,
+    config::{Config, SimilarityConfig, Tag},
     context::BuildConfig,
     diagnostic::{BarDiagnostic, ContextExt},
+    excerpt::{DEFAULT_EXCERPT_WORDS, EXCERPT_MARKER, Excerpt},
     fs::{canonicalize_with_context, get_files_by_ext_deep},
     gallery::unwrap_gallery,
     image_alt::add_alt_text,
-    metadata::Metadata,
+    metadata::{Metadata, MetadataSource, PageKind},
+    similarity::{Similar, SimilarityIndex},
 };
 
+use chrono::{DateTime, Datelike, FixedOffset};
 use futures_core::Stream;
 use img2text::Img2Text;
 use itertools::Itertools;
 use serde::Serialize;
 use std::{
     cmp::Ordering,
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::Bound,
     path::PathBuf,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, OnceLock},
 };
 use tokio::fs::read_to_string;
 use tokio_stream::StreamExt;
@@ -29,11 +34,25 @@ use yamd::op::{self, Node, Op, OpKind};
 #[derive(Debug, Serialize)]
 pub struct Page {
     pub pid: Arc<str>,
+    /// URL path the page is published at.
+    pub path: Arc<str>,
     #[serde(skip)]
     pub ops: Vec<Op>,
     #[serde(skip)]
     pub source: String,
     pub metadata: Metadata,
+    /// Tags of `metadata`, case-folded and resolved through registry aliases when the page is
+    /// pushed to `Pages`.
+    pub tags: Vec<Arc<str>>,
+    #[serde(flatten)]
+    pub stats: ContentStats,
+    /// Plain text beginning of the content, see [`Excerpt`].
+    pub excerpt: String,
+    #[serde(skip)]
+    pub excerpt_ranges: Vec<std::ops::Range<usize>>,
+    /// Anchors of the content, in document order.
+    #[serde(skip)]
+    pub links: Vec<Link>,
 }
 
 impl PartialEq for Page {
@@ -62,6 +81,135 @@ impl Ord for Page {
     }
 }
 
+pub const DEFAULT_WORDS_PER_MINUTE: usize = 200;
+
+/// Size of the page content, counted from the op stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ContentStats {
+    /// Words of prose. Code blocks, metadata, image and link targets are not counted.
+    pub word_count: usize,
+    /// Non-whitespace characters of prose.
+    pub character_count: usize,
+    /// Words inside code blocks.
+    pub code_word_count: usize,
+    /// Estimated minutes to read the prose, at least 1 for pages with any words.
+    pub reading_time: usize,
+}
+
+impl ContentStats {
+    #[must_use]
+    pub fn new(ops: &[Op], source: &str, words_per_minute: usize) -> Self {
+        let (prose, code) = plain_text(ops, source);
+        let stats = Self {
+            word_count: prose.split_whitespace().count(),
+            character_count: prose.chars().filter(|c| !c.is_whitespace()).count(),
+            code_word_count: code.split_whitespace().count(),
+            reading_time: 0,
+        };
+        stats.with_words_per_minute(words_per_minute)
+    }
+
+    #[must_use]
+    pub fn with_words_per_minute(mut self, words_per_minute: usize) -> Self {
+        self.reading_time = self.word_count.div_ceil(words_per_minute.max(1));
+        self
+    }
+}
+
+/// Prose and code block text of `ops`, with a space at every block boundary.
+pub(crate) fn plain_text(ops: &[Op], source: &str) -> (String, String) {
+    let mut stack: Vec<&Node> = vec![];
+    let mut prose = String::new();
+    let mut code = String::new();
+    for op in ops {
+        match &op.kind {
+            OpKind::Start(node) => {
+                stack.push(node);
+                if !is_inline(node) {
+                    prose.push(' ');
+                }
+            }
+            OpKind::End(node) => {
+                stack.pop();
+                if !is_inline(node) {
+                    prose.push(' ');
+                }
+            }
+            OpKind::Value => {
+                if stack.iter().any(|node| is_hidden(node)) {
+                    continue;
+                }
+                let text = op.content.as_str(source);
+                if stack.iter().any(|node| matches!(node, Node::Code)) {
+                    if !matches!(stack.last(), Some(Node::Modifier)) {
+                        code.push_str(text);
+                        code.push(' ');
+                    }
+                } else if text.trim() != EXCERPT_MARKER {
+                    prose.push_str(text);
+                }
+            }
+        }
+    }
+    (prose, code)
+}
+
+fn anchor_links(ops: &[Op], source: &str) -> Vec<Link> {
+    let mut links = vec![];
+    let mut current: Option<(Link, Option<&Node>)> = None;
+    for op in ops {
+        match (&op.kind, current.as_mut()) {
+            (OpKind::Start(Node::Anchor), _) => {
+                current = Some((
+                    Link {
+                        text: String::new(),
+                        destination: String::new(),
+                    },
+                    None,
+                ));
+            }
+            (OpKind::End(Node::Anchor), Some(_)) => {
+                if let Some((link, _)) = current.take() {
+                    links.push(link);
+                }
+            }
+            (OpKind::Start(node), Some((_, part))) => *part = Some(node),
+            (OpKind::End(_), Some((_, part))) => *part = None,
+            (OpKind::Value, Some((link, Some(Node::Title)))) => {
+                link.text.push_str(op.content.as_str(source));
+            }
+            (OpKind::Value, Some((link, Some(Node::Destination)))) => {
+                link.destination.push_str(op.content.as_str(source));
+            }
+            _ => {}
+        }
+    }
+    links
+}
+
+/// Nodes rendered inside the surrounding text, no word break at their end.
+fn is_inline(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Anchor
+            | Node::Bold
+            | Node::CodeSpan
+            | Node::Emphasis
+            | Node::Italic
+            | Node::Strikethrough
+            | Node::Title
+            | Node::Destination
+    )
+}
+
+/// Nodes whose values are not read as text.
+fn is_hidden(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Metadata | Node::Destination | Node::Image | Node::Images | Node::Embed | Node::Icon
+    )
+}
+
 #[derive(Debug, Serialize)]
 pub struct SliceNumber {
     number: usize,
@@ -69,26 +217,364 @@ pub struct SliceNumber {
     display: usize,
 }
 
+/// Language version of a page for `<link rel="alternate" hreflang="...">`.
 #[derive(Debug, Serialize)]
-pub struct PagesSlice {
+pub struct Alternate {
+    lang: Arc<str>,
+    path: Arc<str>,
+    url: Url,
+}
+
+/// Anchor in page content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub text: String,
+    pub destination: String,
+}
+
+/// Scheme of links to pages by pid, e.g. `pid:/travel/alps#day-2`.
+pub const PID_LINK_PREFIX: &str = "pid:";
+
+/// Pid and heading anchor of a `pid:` link destination, `None` for other destinations.
+#[must_use]
+pub fn parse_pid_link(destination: &str) -> Option<(String, Option<&str>)> {
+    let reference = destination.trim().strip_prefix(PID_LINK_PREFIX)?;
+    let (pid, fragment) = reference
+        .split_once('#')
+        .map_or((reference, None), |(pid, fragment)| (pid, Some(fragment)));
+    Some((format!("/{}", pid.trim_start_matches('/')), fragment))
+}
+
+/// Page linking to another page, with the text of its first link there.
+#[derive(Debug, Serialize)]
+pub struct Backlink {
+    pub page: Arc<Page>,
+    pub text: String,
+}
+
+/// Links between content pages, for visualizations.
+#[derive(Debug, Serialize)]
+pub struct LinkGraph {
+    nodes: Vec<LinkGraphNode>,
+    edges: Vec<LinkGraphEdge>,
+}
+
+#[derive(Debug, Serialize)]
+struct LinkGraphNode {
+    pid: Arc<str>,
+    path: Arc<str>,
+    title: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LinkGraphEdge {
+    source: Arc<str>,
+    target: Arc<str>,
+    text: String,
+}
+
+/// File name (without `.yamd`) of the page holding section metadata and body.
+pub const SECTION_INDEX: &str = "_index";
+
+#[derive(Debug, Default)]
+struct Section {
+    /// URL path of the section index page.
+    path: Arc<str>,
+    index: Option<Arc<Page>>,
     pages: BTreeSet<Arc<Page>>,
+    subsections: BTreeSet<Arc<str>>,
+}
+
+/// Section as exposed to templates.
+#[derive(Debug, Serialize)]
+pub struct SectionInfo {
+    /// Section key, e.g. `/travel/europe`.
+    pub section: Arc<str>,
+    /// Last segment of the key, empty for the content root.
+    pub name: Arc<str>,
+    /// `_index.yamd` title, or `name`.
+    pub title: Arc<str>,
+    /// URL path of the section index page.
+    pub path: Arc<str>,
+    pub index: Option<Arc<Page>>,
+    /// Number of pages directly in the section.
+    pub count: usize,
+    pub subsections: Vec<Arc<str>>,
+}
+
+/// `_index`, or `_index.<lang>` for a translation.
+fn is_section_index(name: &str) -> bool {
+    name.split('.').next() == Some(SECTION_INDEX)
+}
+
+fn section_key(dir: &str) -> Arc<str> {
+    format!("/{}", dir.trim_matches('/')).into()
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryCrumb {
+    /// Last segment of the path.
+    name: Arc<str>,
+    /// Full category path, e.g. `travel/europe`.
+    path: Arc<str>,
+    /// URL-safe slug of every segment joined with `/`.
+    slug: String,
+}
+
+impl CategoryCrumb {
+    fn new(path: &str) -> Self {
+        Self {
+            name: path.rsplit('/').next().unwrap_or(path).into(),
+            slug: path.split('/').map(tag_slug).join("/"),
+            path: path.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    crumb: CategoryCrumb,
+    /// Number of articles in the category and its subcategories.
+    count: usize,
+    children: Vec<CategoryNode>,
+}
+
+/// Trimmed segments joined with `/`, empty segments dropped.
+fn normalize_category(category: &str) -> Arc<str> {
+    category
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .join("/")
+        .into()
+}
+
+fn category_parent(path: &str) -> Option<&str> {
+    path.rsplit_once('/').map(|(parent, _)| parent)
+}
+
+/// Tag as exposed to templates.
+#[derive(Debug, Serialize)]
+pub struct TagInfo {
+    /// Normalized tag, as stored in article metadata.
+    tag: Arc<str>,
+    name: Arc<str>,
+    slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<Arc<str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<Arc<str>>,
+    /// Number of articles with the tag.
+    count: usize,
+}
+
+fn fold_tag(tag: &str) -> Arc<str> {
+    tag.trim().to_lowercase().into()
+}
+
+/// URL slug of the tag, see [`url_slug`].
+#[must_use]
+pub fn tag_slug(tag: &str) -> String {
+    url_slug(tag)
+}
+
+/// Lowercase ASCII transliteration of `text` with every run of other characters replaced by
+/// one `-`, e.g. `Café Crème!` becomes `cafe-creme`.
+#[must_use]
+pub fn url_slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in deunicode::deunicode(text).chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(slug.trim_end_matches('-').len());
+    slug
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveYear {
+    year: i32,
+    count: usize,
+    months: Vec<ArchiveMonth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveMonth {
+    month: u32,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesNav {
+    name: Arc<str>,
+    /// 1-based position in the series.
+    position: usize,
+    total: usize,
+    prev: Option<Arc<Page>>,
+    next: Option<Arc<Page>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PagesSlice {
+    pages: Vec<Arc<Page>>,
     current_slice: usize,
     total_slices: usize,
     numbers: Vec<SliceNumber>,
     slice_size: usize,
 }
 
+impl PagesSlice {
+    /// The `offset..offset + limit` slice of `pages`, numbered as slices of `limit` pages.
+    /// Pages keep the order of the iterator. A `limit` of 0 is treated as 1.
+    #[must_use]
+    pub fn new<'a, I>(pages: I, limit: usize, offset: usize) -> Self
+    where
+        I: IntoIterator<Item = &'a Arc<Page>>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let limit = limit.max(1);
+        let pages = pages.into_iter();
+        let current_slice = offset / limit;
+        let total_slices: usize = pages.len().div_ceil(limit);
+
+        let mut numbers: Vec<SliceNumber> = Vec::with_capacity(total_slices);
+
+        for i in 0..total_slices {
+            numbers.push(SliceNumber {
+                number: i,
+                display: i + 1,
+                is_current: i == current_slice,
+            });
+        }
+
+        PagesSlice {
+            pages: pages.skip(offset).take(limit).cloned().collect(),
+            current_slice,
+            total_slices,
+            slice_size: limit,
+            numbers,
+        }
+    }
+}
+
+/// Filters and ordering for `Pages::query`. Empty filters match every article.
+#[derive(Debug, Default)]
+pub struct PageQuery {
+    /// Article has at least one of these tags.
+    pub any_tags: Vec<Arc<str>>,
+    /// Article has every one of these tags.
+    pub all_tags: Vec<Arc<str>>,
+    /// Article has none of these tags.
+    pub exclude_tags: Vec<Arc<str>>,
+    /// Inclusive lower bound of the article date.
+    pub from: Option<DateTime<FixedOffset>>,
+    /// Exclusive upper bound of the article date.
+    pub to: Option<DateTime<FixedOffset>>,
+    /// Metadata key and the value it must be equal to.
+    pub fields: Vec<(Arc<str>, serde_json::Value)>,
+    /// `date` (default), `title` or any other metadata key.
+    pub sort_by: Option<Arc<str>>,
+    pub ascending: bool,
+}
+
+impl PageQuery {
+    fn matches(&self, page: &Page, metadata: &serde_json::Value) -> bool {
+        let has = |tag: &Arc<str>| page.tags.contains(tag);
+        (self.any_tags.is_empty() || self.any_tags.iter().any(has))
+            && self.all_tags.iter().all(has)
+            && !self.exclude_tags.iter().any(has)
+            && self.from.is_none_or(|from| page.metadata.date >= from)
+            && self.to.is_none_or(|to| page.metadata.date < to)
+            && self
+                .fields
+                .iter()
+                .all(|(key, value)| metadata.get(key.as_ref()) == Some(value))
+    }
+
+    fn cmp(
+        &self,
+        (left, left_metadata): &(Arc<Page>, serde_json::Value),
+        (right, right_metadata): &(Arc<Page>, serde_json::Value),
+    ) -> Ordering {
+        let ordering = match self.sort_by.as_deref().unwrap_or("date") {
+            "date" => left.metadata.date.cmp(&right.metadata.date),
+            "title" => left.metadata.title.cmp(&right.metadata.title),
+            key => {
+                let value = |metadata: &serde_json::Value| {
+                    metadata.get(key).filter(|value| !value.is_null()).cloned()
+                };
+                match (value(left_metadata), value(right_metadata)) {
+                    (Some(left), Some(right)) => cmp_json(&left, &right),
+                    // pages without the field go last in both directions
+                    (Some(_), None) => return Ordering::Less,
+                    (None, Some(_)) => return Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
+        };
+        if self.ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+}
+
+fn cmp_json(left: &serde_json::Value, right: &serde_json::Value) -> Ordering {
+    use serde_json::Value;
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left
+            .as_f64()
+            .partial_cmp(&right.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(left), Value::String(right)) => left.cmp(right),
+        (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
+        _ => left.to_string().cmp(&right.to_string()),
+    }
+}
+
 impl Page {
     #[must_use]
     pub fn new(pid: Arc<str>, ops: Vec<Op>, source: String, metadata: Metadata) -> Self {
+        let excerpt = Excerpt::new(&ops, &source, DEFAULT_EXCERPT_WORDS);
         Self {
+            path: format!("{pid}.html").into(),
+            stats: ContentStats::new(&ops, &source, DEFAULT_WORDS_PER_MINUTE),
+            excerpt: excerpt.text,
+            excerpt_ranges: excerpt.ranges,
+            links: anchor_links(&ops, &source),
             pid,
             ops,
             source,
             metadata,
+            tags: vec![],
         }
     }
 
+    #[must_use]
+    pub fn with_words_per_minute(mut self, words_per_minute: usize) -> Self {
+        self.stats = self.stats.with_words_per_minute(words_per_minute);
+        self
+    }
+
+    #[must_use]
+    pub fn with_excerpt_words(mut self, words: usize) -> Self {
+        let excerpt = Excerpt::new(&self.ops, &self.source, words);
+        self.excerpt = excerpt.text;
+        self.excerpt_ranges = excerpt.ranges;
+        self
+    }
+
+    #[must_use]
+    pub fn with_path(mut self, path: impl Into<Arc<str>>) -> Self {
+        self.path = path.into();
+        self
+    }
+
     #[must_use]
     pub fn get_title(&self) -> String {
         self.metadata.title.clone()
@@ -109,9 +595,38 @@ impl Page {
     }
 }
 
+#[allow(clippy::struct_field_names)]
 pub struct Pages {
     pages: HashMap<Arc<str>, Arc<Page>>,
+    /// Articles in `Page::cmp` order: newest first.
+    articles: BTreeSet<Arc<Page>>,
+    paths: HashMap<Arc<str>, Arc<str>>,
     tags: HashMap<Arc<str>, BTreeSet<Arc<Page>>>,
+    authors: HashMap<Arc<str>, BTreeSet<Arc<Page>>>,
+    series: HashMap<Arc<str>, Vec<Arc<Page>>>,
+    /// Content directories by key (`/` for the content root, `/travel` for a subdirectory).
+    sections: BTreeMap<Arc<str>, Section>,
+    /// Section index URL path to section key.
+    section_paths: HashMap<Arc<str>, Arc<str>>,
+    /// Articles by category path, including articles of every subcategory.
+    categories: BTreeMap<Arc<str>, BTreeSet<Arc<Page>>>,
+    /// Articles by year, then month of their date.
+    archive: BTreeMap<i32, BTreeMap<u32, BTreeSet<Arc<Page>>>>,
+    /// Tags registry from config, keyed by folded tag.
+    tag_registry: HashMap<Arc<str>, Tag>,
+    /// Folded alias to the tag it stands for.
+    tag_aliases: HashMap<Arc<str>, Arc<str>>,
+    /// Tag as first written in an article, used when the registry has no name.
+    tag_spellings: HashMap<Arc<str>, Arc<str>>,
+    /// Pages by translation key, ordered by language.
+    translations: HashMap<Arc<str>, Vec<Arc<Page>>>,
+    /// Pages of a single language, only when content has several languages.
+    languages: HashMap<Arc<str>, Arc<Pages>>,
+    /// Pages linking to a pid, in `Page::cmp` order.
+    backlinks: HashMap<Arc<str>, Vec<Backlink>>,
+    similarity_weights: SimilarityConfig,
+    /// Built on first `get_similar`, reset when pages change.
+    similarity: OnceLock<SimilarityIndex>,
 }
 
 impl Pages {
@@ -119,8 +634,82 @@ impl Pages {
     pub fn new() -> Self {
         Self {
             pages: HashMap::new(),
+            articles: BTreeSet::new(),
+            paths: HashMap::new(),
             tags: HashMap::new(),
+            authors: HashMap::new(),
+            series: HashMap::new(),
+            sections: BTreeMap::new(),
+            section_paths: HashMap::new(),
+            categories: BTreeMap::new(),
+            archive: BTreeMap::new(),
+            tag_registry: HashMap::new(),
+            tag_aliases: HashMap::new(),
+            tag_spellings: HashMap::new(),
+            translations: HashMap::new(),
+            languages: HashMap::new(),
+            backlinks: HashMap::new(),
+            similarity_weights: SimilarityConfig::default(),
+            similarity: OnceLock::new(),
+        }
+    }
+
+    /// Use the tags registry for tag names and aliases. Call before pushing pages.
+    #[must_use]
+    pub fn with_tag_registry(mut self, registry: &HashMap<Arc<str>, Tag>) -> Self {
+        for (tag, info) in registry {
+            let tag = fold_tag(tag);
+            for alias in &info.aliases {
+                self.tag_aliases.insert(fold_tag(alias), tag.clone());
+            }
+            self.tag_registry.insert(tag, info.clone());
+        }
+        self
+    }
+
+    /// Use the weights for `get_similar` instead of the defaults.
+    #[must_use]
+    pub fn with_similarity(mut self, weights: SimilarityConfig) -> Self {
+        self.similarity_weights = weights;
+        self.similarity = OnceLock::new();
+        self
+    }
+
+    /// Case-folded tag, resolved through registry aliases.
+    #[must_use]
+    pub fn normalize_tag(&self, tag: &str) -> Arc<str> {
+        let tag = fold_tag(tag);
+        self.tag_aliases.get(&tag).cloned().unwrap_or(tag)
+    }
+
+    /// Display name of the tag: registry name, else spelling from the first article using it.
+    #[must_use]
+    pub fn tag_name(&self, tag: &str) -> Arc<str> {
+        let tag = self.normalize_tag(tag);
+        self.tag_registry
+            .get(&tag)
+            .and_then(|info| info.name.clone())
+            .or_else(|| self.tag_spellings.get(&tag).cloned())
+            .unwrap_or(tag)
+    }
+
+    /// Tag with its registry entry and article count, `None` if neither used nor registered.
+    #[must_use]
+    pub fn get_tag(&self, tag: &str) -> Option<TagInfo> {
+        let tag = self.normalize_tag(tag);
+        let info = self.tag_registry.get(&tag);
+        let count = self.tags.get(&tag).map_or(0, BTreeSet::len);
+        if info.is_none() && count == 0 {
+            return None;
         }
+        Some(TagInfo {
+            name: self.tag_name(&tag),
+            slug: tag_slug(&tag),
+            description: info.and_then(|info| info.description.clone()),
+            image: info.and_then(|info| info.image.clone()),
+            count,
+            tag,
+        })
     }
 
     pub fn add(&mut self, key: &str, ops: Vec<Op>, source: String, metadata: Metadata) {
@@ -128,16 +717,127 @@ impl Pages {
         self.push(Page::new(pid, ops, source, metadata));
     }
 
-    pub fn push(&mut self, page: Page) {
-        let page = Arc::new(page);
+    /// Push the page unless another page is already published at its path.
+    ///
+    /// # Errors
+    /// Returns error naming both pages if their paths collide.
+    pub fn try_push(&mut self, page: Page) -> Result<(), BarDiagnostic> {
+        if let Some(other) = self
+            .get_by_path(&page.path)
+            .filter(|other| other.path == page.path)
+        {
+            return Err(BarDiagnostic::new(format!(
+                "{} and {} are both published at {}",
+                other.pid, page.pid, page.path
+            ))
+            .with_help("set a different `slug` in metadata of one of them"));
+        }
+        self.push(page);
+        Ok(())
+    }
+
+    pub fn push(&mut self, mut page: Page) {
+        page.tags.clear();
+        for tag in page.metadata.tags.iter().flatten() {
+            let key = self.normalize_tag(tag);
+            self.tag_spellings
+                .entry(key.clone())
+                .or_insert_with(|| tag.trim().into());
+            if !page.tags.contains(&key) {
+                page.tags.push(key);
+            }
+        }
+        if let Some(categories) = page.metadata.categories.take() {
+            page.metadata.categories = Some(
+                categories
+                    .iter()
+                    .map(|category| normalize_category(category))
+                    .filter(|category| !category.is_empty())
+                    .unique()
+                    .collect(),
+            );
+        }
+        if page
+            .pid
+            .rsplit_once('/')
+            .is_some_and(|(_, name)| is_section_index(name))
+        {
+            page.metadata.kind = PageKind::Page;
+        }
+        self.insert(&Arc::new(page));
+    }
+
+    /// Add the already normalized page to every index.
+    fn insert(&mut self, page: &Arc<Page>) {
+        let section = page
+            .pid
+            .rsplit_once('/')
+            .map(|(parent, name)| (section_key(parent), is_section_index(name)));
+        if let Some((key, is_index)) = section {
+            let section = self.add_section(key);
+            if is_index {
+                section.index = Some(page.clone());
+            } else {
+                section.pages.insert(page.clone());
+            }
+        }
 
+        self.similarity = OnceLock::new();
         self.pages.insert(page.pid.clone(), page.clone());
+        self.paths.insert(page.path.clone(), page.pid.clone());
 
-        let Some(tags) = &page.metadata.tags else {
+        if let Some(key) = &page.metadata.translation_key {
+            let translations = self.translations.entry(key.clone()).or_default();
+            translations.push(page.clone());
+            translations.sort_by(|left, right| left.metadata.lang.cmp(&right.metadata.lang));
+        }
+
+        if let Some(name) = &page.metadata.series {
+            let series = self.series.entry(name.clone()).or_default();
+            series.push(page.clone());
+            series.sort_by(|left, right| {
+                let key = |page: &Page| {
+                    (
+                        page.metadata.series_order.is_none(),
+                        page.metadata.series_order,
+                        page.metadata.date,
+                    )
+                };
+                key(left)
+                    .cmp(&key(right))
+                    .then_with(|| left.pid.cmp(&right.pid))
+            });
+        }
+
+        if !page.metadata.is_article() {
             return;
-        };
+        }
+
+        self.articles.insert(page.clone());
+        self.archive
+            .entry(page.metadata.date.year())
+            .or_default()
+            .entry(page.metadata.date.month())
+            .or_default()
+            .insert(page.clone());
+
+        for category in page.metadata.categories.iter().flatten() {
+            for (end, _) in category.match_indices('/').chain([(category.len(), "")]) {
+                self.categories
+                    .entry(category[..end].into())
+                    .or_default()
+                    .insert(page.clone());
+            }
+        }
+
+        for author in page.metadata.authors.iter().flatten() {
+            self.authors
+                .entry(author.clone())
+                .or_default()
+                .insert(page.clone());
+        }
 
-        for tag in tags {
+        for tag in &page.tags {
             self.tags
                 .entry(tag.clone())
                 .and_modify(|pages| {
@@ -157,6 +857,14 @@ impl Pages {
         self.pages.get(pid).map(std::convert::AsRef::as_ref)
     }
 
+    /// Content page published at `path`. A trailing `index.html` is ignored. Pages moved by
+    /// pretty URLs or a permalink are not found at `{pid}.html`.
+    #[must_use]
+    pub fn get_by_path(&self, path: &str) -> Option<&Page> {
+        let path = path.strip_suffix("index.html").unwrap_or(path);
+        self.paths.get(path).and_then(|pid| self.get(pid))
+    }
+
     #[must_use]
     pub fn get_tags(&self) -> HashSet<Arc<str>> {
         let mut tags: HashSet<Arc<str>> = HashSet::new();
@@ -167,82 +875,560 @@ impl Pages {
         tags
     }
 
+    /// All articles, or articles with the tag, newest first.
     #[must_use]
-    pub fn get_posts_by_tag(&self, tag: &str, limit: usize, offset: usize) -> Option<PagesSlice> {
-        let pages = self.tags.get(tag)?;
-
-        let current_slice = offset / limit;
-        let total_slices: usize = pages.len().div_ceil(limit);
-
-        let mut numbers: Vec<SliceNumber> = Vec::with_capacity(total_slices);
-
-        for i in 0..total_slices {
-            numbers.push(SliceNumber {
-                number: i,
-                display: i + 1,
-                is_current: i == current_slice,
-            });
+    pub fn get_articles(&self, tag: Option<&str>) -> Option<&BTreeSet<Arc<Page>>> {
+        match tag {
+            Some(tag) => self.tags.get(&self.normalize_tag(tag)),
+            None => Some(&self.articles),
         }
-        let mut slice = PagesSlice {
-            pages: BTreeSet::new(),
-            current_slice,
-            total_slices,
-            slice_size: limit,
-            numbers,
-        };
+    }
 
-        for page in pages.iter().skip(offset).take(limit) {
-            slice.pages.insert(page.clone());
-        }
-        Some(slice)
+    #[must_use]
+    pub fn get_posts_by_tag(&self, tag: &str, limit: usize, offset: usize) -> Option<PagesSlice> {
+        let pages = self.tags.get(&self.normalize_tag(tag))?;
+        Some(PagesSlice::new(pages, limit, offset))
     }
 
     #[must_use]
-    pub fn get_similar(&self, pid: &str, max: usize) -> Vec<Arc<str>> {
-        let Some(page) = self.get(pid) else {
-            return vec![];
-        };
+    pub fn get_posts_by_author(
+        &self,
+        author: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Option<PagesSlice> {
+        let pages = self.authors.get(author)?;
+        Some(PagesSlice::new(pages, limit, offset))
+    }
 
-        let Some(tags) = page.metadata.tags.as_ref() else {
-            return vec![];
+    /// Articles matching the query in its sort order. Ties keep `Page::cmp` order.
+    #[must_use]
+    pub fn query(&self, query: &PageQuery) -> Vec<Arc<Page>> {
+        let normalize = |tags: &[Arc<str>]| -> Vec<Arc<str>> {
+            tags.iter().map(|tag| self.normalize_tag(tag)).collect()
         };
+        let query = &PageQuery {
+            any_tags: normalize(&query.any_tags),
+            all_tags: normalize(&query.all_tags),
+            exclude_tags: normalize(&query.exclude_tags),
+            from: query.from,
+            to: query.to,
+            fields: query.fields.clone(),
+            sort_by: query.sort_by.clone(),
+            ascending: query.ascending,
+        };
+        let mut found: Vec<(Arc<Page>, serde_json::Value)> = self
+            .articles
+            .iter()
+            .filter_map(|page| {
+                let metadata = serde_json::to_value(&page.metadata).unwrap_or_default();
+                query
+                    .matches(page, &metadata)
+                    .then(|| (page.clone(), metadata))
+            })
+            .collect();
+        found.sort_by(|left, right| query.cmp(left, right));
+        found.into_iter().map(|(page, _)| page).collect()
+    }
+
+    /// Section with the key, registering it in every ancestor section.
+    fn add_section(&mut self, key: Arc<str>) -> &mut Section {
+        let mut child = key.clone();
+        while let Some((parent, _)) = child.rsplit_once('/') {
+            let parent = section_key(parent);
+            if parent == child {
+                break;
+            }
+            self.sections
+                .entry(parent.clone())
+                .or_default()
+                .subsections
+                .insert(child);
+            child = parent;
+        }
+        self.sections.entry(key).or_default()
+    }
 
-        let mut leaderboard: HashMap<Arc<str>, usize> = HashMap::new();
+    /// Remember the URL path of every section index page.
+    pub fn set_section_paths(&mut self, path: impl Fn(&str) -> String) {
+        for (key, section) in &mut self.sections {
+            let section_path: Arc<str> = path(key).into();
+            self.section_paths.insert(section_path.clone(), key.clone());
+            section.path = section_path;
+        }
+    }
 
-        for tag in tags {
-            let Some(tag_pages) = self.tags.get(tag) else {
-                continue;
+    /// Build a view of every language found in page metadata, if there is more than one.
+    /// `section_path` gets the language and the section key.
+    pub fn split_languages(&mut self, section_path: impl Fn(&str, &str) -> String) {
+        let languages: BTreeSet<Arc<str>> = self
+            .pages
+            .values()
+            .filter_map(|page| page.metadata.lang.clone())
+            .collect();
+        if languages.len() < 2 {
+            return;
+        }
+        for lang in languages {
+            let mut view = Pages {
+                tag_registry: self.tag_registry.clone(),
+                tag_aliases: self.tag_aliases.clone(),
+                tag_spellings: self.tag_spellings.clone(),
+                similarity_weights: self.similarity_weights,
+                ..Pages::new()
             };
-            for other in tag_pages {
-                if page.pid == other.pid {
-                    continue;
+            for page in self.pages.values() {
+                if page.metadata.lang.as_ref() == Some(&lang) {
+                    view.insert(page);
                 }
-
-                leaderboard
-                    .entry(other.pid.clone())
-                    .and_modify(|score| *score += 1)
-                    .or_insert(1);
             }
+            view.translations.clone_from(&self.translations);
+            view.set_section_paths(|section| section_path(&lang, section));
+            self.languages.insert(lang, Arc::new(view));
         }
+    }
 
-        let leaderboard: Vec<(&Arc<str>, &usize)> = leaderboard
-            .iter()
-            .sorted_by(|(_, left), (_, right)| right.cmp(left))
-            .collect();
-
-        leaderboard
-            .iter()
-            .take(max)
-            .map(|(pid, _)| (*pid).clone())
-            .collect()
+    /// Pages in the language, `self` for `None` or when content has a single language.
+    #[must_use]
+    pub fn in_language(&self, lang: Option<&str>) -> Option<&Pages> {
+        match lang {
+            Some(lang) if !self.languages.is_empty() => self.languages.get(lang).map(AsRef::as_ref),
+            _ => Some(self),
+        }
     }
-}
 
-impl Default for Pages {
-    fn default() -> Self {
-        Self::new()
+    /// Languages with their own view, sorted.
+    #[must_use]
+    pub fn languages(&self) -> Vec<Arc<str>> {
+        self.languages.keys().cloned().sorted().collect()
     }
-}
+
+    /// Every version of the page, including itself, ordered by language.
+    #[must_use]
+    pub fn get_translations(&self, pid: &str) -> &[Arc<Page>] {
+        self.get(pid)
+            .and_then(|page| page.metadata.translation_key.as_ref())
+            .and_then(|key| self.translations.get(key))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Version of the page in the language.
+    #[must_use]
+    pub fn get_translation(&self, pid: &str, lang: &str) -> Option<Arc<Page>> {
+        self.get_translations(pid)
+            .iter()
+            .find(|page| page.metadata.lang.as_deref() == Some(lang))
+            .cloned()
+    }
+
+    /// `hreflang` alternates of the page; empty if it has no translations.
+    #[must_use]
+    pub fn get_alternates(&self, pid: &str, base_url: &Url) -> Vec<Alternate> {
+        let translations = self.get_translations(pid);
+        if translations.len() < 2 {
+            return vec![];
+        }
+        translations
+            .iter()
+            .filter_map(|page| {
+                Some(Alternate {
+                    lang: page.metadata.lang.clone()?,
+                    path: page.path.clone(),
+                    url: base_url.join(&page.path).ok()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Section keys, content root first.
+    #[must_use]
+    pub fn section_keys(&self) -> Vec<Arc<str>> {
+        self.sections.keys().cloned().collect()
+    }
+
+    /// Section by key; `travel`, `/travel` and `/travel/` are the same section.
+    #[must_use]
+    pub fn get_section(&self, section: &str) -> Option<SectionInfo> {
+        let key = section_key(section);
+        let section = self.sections.get(&key)?;
+        let name: Arc<str> = key.rsplit('/').next().unwrap_or_default().into();
+        Some(SectionInfo {
+            title: section
+                .index
+                .as_ref()
+                .map(|index| index.metadata.title.as_str())
+                .filter(|title| !title.is_empty())
+                .map_or_else(|| name.clone(), Arc::from),
+            name,
+            path: section.path.clone(),
+            index: section.index.clone(),
+            count: section.pages.len(),
+            subsections: section.subsections.iter().cloned().collect(),
+            section: key,
+        })
+    }
+
+    /// Section whose index page is published at the URL path.
+    #[must_use]
+    pub fn get_section_by_path(&self, path: &str) -> Option<SectionInfo> {
+        if self.languages.is_empty() {
+            return self.get_section(self.section_paths.get(path)?);
+        }
+        self.languages
+            .values()
+            .find_map(|view| view.get_section_by_path(path))
+    }
+
+    /// Pages directly in the section, `_index` excluded.
+    #[must_use]
+    pub fn get_posts_by_section(
+        &self,
+        section: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Option<PagesSlice> {
+        let section = self.sections.get(&section_key(section))?;
+        Some(PagesSlice::new(&section.pages, limit, offset))
+    }
+
+    /// Articles in the category or any of its subcategories.
+    #[must_use]
+    pub fn get_posts_by_category(
+        &self,
+        category: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Option<PagesSlice> {
+        let pages = self.categories.get(normalize_category(category).as_ref())?;
+        Some(PagesSlice::new(pages, limit, offset))
+    }
+
+    /// Categories as a tree, or the subtree under `root` if given.
+    #[must_use]
+    pub fn get_category_tree(&self, root: Option<&str>) -> Vec<CategoryNode> {
+        let root = root.map(normalize_category);
+        self.category_children(root.as_deref())
+    }
+
+    fn category_children(&self, parent: Option<&str>) -> Vec<CategoryNode> {
+        self.categories
+            .iter()
+            .filter(|(path, _)| category_parent(path) == parent)
+            .map(|(path, pages)| CategoryNode {
+                crumb: CategoryCrumb::new(path),
+                count: pages.len(),
+                children: self.category_children(Some(path)),
+            })
+            .collect()
+    }
+
+    /// The category and its ancestors, root first. `None` if no article uses the category.
+    #[must_use]
+    pub fn get_category_breadcrumbs(&self, category: &str) -> Option<Vec<CategoryCrumb>> {
+        let category = normalize_category(category);
+        if !self.categories.contains_key(&category) {
+            return None;
+        }
+        Some(
+            category
+                .match_indices('/')
+                .map(|(end, _)| &category[..end])
+                .chain([category.as_ref()])
+                .map(CategoryCrumb::new)
+                .collect(),
+        )
+    }
+
+    /// Number of articles per year and month, newest period first.
+    #[must_use]
+    pub fn get_archive(&self) -> Vec<ArchiveYear> {
+        self.archive
+            .iter()
+            .rev()
+            .map(|(year, months)| ArchiveYear {
+                year: *year,
+                count: months.values().map(BTreeSet::len).sum(),
+                months: months
+                    .iter()
+                    .rev()
+                    .map(|(month, pages)| ArchiveMonth {
+                        month: *month,
+                        count: pages.len(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Articles published in the year, or in the month of the year if given.
+    #[must_use]
+    pub fn get_posts_by_period(
+        &self,
+        year: i32,
+        month: Option<u32>,
+        limit: usize,
+        offset: usize,
+    ) -> Option<PagesSlice> {
+        let months = self.archive.get(&year)?;
+        let pages = match month {
+            Some(month) => months.get(&month)?.clone(),
+            None => months.values().flatten().cloned().collect(),
+        };
+        Some(PagesSlice::new(&pages, limit, offset))
+    }
+
+    /// Article published right before the page, among articles with `tag` if given.
+    #[must_use]
+    pub fn get_prev(&self, pid: &str, tag: Option<&str>) -> Option<Arc<Page>> {
+        let (page, articles) = self.neighbours_of(pid, tag)?;
+        articles
+            .range::<Arc<Page>, _>((Bound::Excluded(page), Bound::Unbounded))
+            .next()
+            .cloned()
+    }
+
+    /// Article published right after the page, among articles with `tag` if given.
+    #[must_use]
+    pub fn get_next(&self, pid: &str, tag: Option<&str>) -> Option<Arc<Page>> {
+        let (page, articles) = self.neighbours_of(pid, tag)?;
+        articles
+            .range::<Arc<Page>, _>((Bound::Unbounded, Bound::Excluded(page)))
+            .next_back()
+            .cloned()
+    }
+
+    fn neighbours_of(
+        &self,
+        pid: &str,
+        tag: Option<&str>,
+    ) -> Option<(&Arc<Page>, &BTreeSet<Arc<Page>>)> {
+        let page = self.pages.get(pid)?;
+        let articles = self.get_articles(tag)?;
+        articles.contains(page).then_some((page, articles))
+    }
+
+    /// Pages of the series in reading order: by `series_order`, then oldest first.
+    #[must_use]
+    pub fn get_series(&self, name: &str) -> Option<&[Arc<Page>]> {
+        self.series.get(name).map(Vec::as_slice)
+    }
+
+    /// Position of the page in its series with its neighbours.
+    #[must_use]
+    pub fn get_series_nav(&self, pid: &str) -> Option<SeriesNav> {
+        let name = self.get(pid)?.metadata.series.clone()?;
+        let series = self.get_series(&name)?;
+        let index = series.iter().position(|page| page.pid.as_ref() == pid)?;
+        Some(SeriesNav {
+            position: index + 1,
+            total: series.len(),
+            prev: index.checked_sub(1).map(|prev| series[prev].clone()),
+            next: series.get(index + 1).cloned(),
+            name,
+        })
+    }
+
+    /// Checks the series of every language on its own, translations of a series share its
+    /// name and order. Call after `split_languages`.
+    ///
+    /// # Errors
+    /// Returns error listing every series page without `series_order`, every `series_order`
+    /// shared by several pages of the same series and every gap in a series' order.
+    pub fn check_series(&self) -> Result<(), BarDiagnostic> {
+        let problems = if self.languages.is_empty() {
+            self.series_problems()
+        } else {
+            self.languages
+                .iter()
+                .sorted_by_key(|(lang, _)| *lang)
+                .flat_map(|(_, view)| view.series_problems())
+                .collect()
+        };
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(problems.into_iter().fold(
+            BarDiagnostic::new("inconsistent series order")
+                .with_help("number the pages of a series one after another, without repeats"),
+            BarDiagnostic::with_related,
+        ))
+    }
+
+    fn series_problems(&self) -> Vec<BarDiagnostic> {
+        let mut problems: Vec<BarDiagnostic> = vec![];
+        for (name, series) in self.series.iter().sorted_by_key(|(name, _)| *name) {
+            for page in series {
+                let Some(order) = page.metadata.series_order else {
+                    problems.push(BarDiagnostic::new(format!(
+                        "{} is in series '{name}' but has no series_order",
+                        page.pid
+                    )));
+                    continue;
+                };
+                if let Some(first) = series
+                    .iter()
+                    .find(|other| other.metadata.series_order == Some(order))
+                    && first.pid != page.pid
+                {
+                    problems.push(BarDiagnostic::new(format!(
+                        "{} and {} share series_order {order} in series '{name}'",
+                        first.pid, page.pid
+                    )));
+                }
+            }
+            let orders = series
+                .iter()
+                .filter_map(|page| page.metadata.series_order)
+                .sorted()
+                .dedup()
+                .collect::<Vec<_>>();
+            for (prev, next) in orders.iter().tuple_windows() {
+                let missing = match next - prev {
+                    1 => continue,
+                    2 => (prev + 1).to_string(),
+                    _ => format!("{} to {}", prev + 1, next - 1),
+                };
+                problems.push(BarDiagnostic::new(format!(
+                    "series '{name}' skips series_order {missing}"
+                )));
+            }
+        }
+        problems
+    }
+
+    /// # Errors
+    /// Returns error listing every slug shared by several used or registered tags, as their
+    /// pages would be published at the same path.
+    pub fn check_tag_slugs(&self) -> Result<(), BarDiagnostic> {
+        let mut slugs: BTreeMap<String, BTreeSet<Arc<str>>> = BTreeMap::new();
+        for tag in self.tags.keys().chain(self.tag_registry.keys()) {
+            slugs.entry(tag_slug(tag)).or_default().insert(tag.clone());
+        }
+        let problems = slugs
+            .into_iter()
+            .filter(|(_, tags)| tags.len() > 1)
+            .map(|(slug, tags)| {
+                BarDiagnostic::new(format!(
+                    "tags {} share slug '{slug}'",
+                    tags.iter()
+                        .map(|tag| format!("'{}'", self.tag_name(tag)))
+                        .join(", ")
+                ))
+            })
+            .collect::<Vec<_>>();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(problems.into_iter().fold(
+            BarDiagnostic::new("tag slugs collide").with_help(
+                "rename one of the tags, or make it an alias of the other in `config.tags`",
+            ),
+            BarDiagnostic::with_related,
+        ))
+    }
+
+    /// Resolves links of every page to content pages and indexes them by target. Relative
+    /// destinations are resolved against the linking page, absolute URLs only count on `domain`,
+    /// `pid:` links point to the page with that pid.
+    /// Links of a page to itself are skipped.
+    pub fn link_pages(&mut self, domain: &Url) {
+        let mut backlinks: HashMap<Arc<str>, Vec<Backlink>> = HashMap::new();
+        for page in self.pages.values() {
+            let Ok(base) = domain.join(&page.path) else {
+                continue;
+            };
+            let mut linked: HashSet<Arc<str>> = HashSet::new();
+            for link in &page.links {
+                let target = match parse_pid_link(&link.destination) {
+                    Some((pid, _)) => self.get(&pid),
+                    None => base
+                        .join(&link.destination)
+                        .ok()
+                        .filter(|url| url.origin() == domain.origin())
+                        .and_then(|url| self.get_by_path(url.path())),
+                };
+                let Some(target) = target else {
+                    continue;
+                };
+                if target.pid == page.pid || !linked.insert(target.pid.clone()) {
+                    continue;
+                }
+                backlinks
+                    .entry(target.pid.clone())
+                    .or_default()
+                    .push(Backlink {
+                        page: page.clone(),
+                        text: link.text.clone(),
+                    });
+            }
+        }
+        for links in backlinks.values_mut() {
+            links.sort_by(|left, right| left.page.cmp(&right.page));
+        }
+        self.backlinks = backlinks;
+    }
+
+    /// Pages linking to `pid`, newest first.
+    #[must_use]
+    pub fn get_backlinks(&self, pid: &str) -> &[Backlink] {
+        self.backlinks.get(pid).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every content page and the links between them, ordered by pid.
+    #[must_use]
+    pub fn get_link_graph(&self) -> LinkGraph {
+        let nodes = self
+            .pages
+            .values()
+            .sorted_by(|left, right| left.pid.cmp(&right.pid))
+            .map(|page| LinkGraphNode {
+                pid: page.pid.clone(),
+                path: page.path.clone(),
+                title: page.get_title(),
+            })
+            .collect();
+        let edges = self
+            .backlinks
+            .iter()
+            .flat_map(|(target, links)| {
+                links.iter().map(|link| LinkGraphEdge {
+                    source: link.page.pid.clone(),
+                    target: target.clone(),
+                    text: link.text.clone(),
+                })
+            })
+            .sorted_by(|left, right| {
+                (&left.source, &left.target).cmp(&(&right.source, &right.target))
+            })
+            .collect();
+        LinkGraph { nodes, edges }
+    }
+
+    /// Up to `max` articles most similar to the page by tags and, with a content weight, by
+    /// text. See [`SimilarityIndex::similar`].
+    #[must_use]
+    pub fn get_similar(&self, pid: &str, max: usize) -> Vec<Similar> {
+        if self.get(pid).is_none() {
+            return vec![];
+        }
+        self.similarity
+            .get_or_init(|| {
+                let pages = self.pages.values().map(|page| (page, page.tags.clone()));
+                SimilarityIndex::new(
+                    self.similarity_weights,
+                    pages,
+                    self.articles.iter().cloned().collect(),
+                )
+            })
+            .similar(pid, max)
+    }
+}
+
+impl Default for Pages {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 async fn path_to_yamd(
     (path, content_path): (PathBuf, Arc<PathBuf>),
@@ -273,158 +1459,880 @@ async fn path_to_yamd(
     Ok((pid, file_contents, ops))
 }
 
-fn extract_metadata<'a>(ops: &'a [Op], source: &'a str) -> Option<&'a str> {
-    let mut in_metadata = false;
-    for op in ops {
-        match &op.kind {
-            OpKind::Start(Node::Metadata) => in_metadata = true,
-            OpKind::Value if in_metadata => return Some(op.content.as_str(source)),
-            OpKind::End(Node::Metadata) => return None,
-            _ => {}
-        }
+/// Byte range of the metadata text in the source.
+fn metadata_range(ops: &[Op]) -> Option<std::ops::Range<usize>> {
+    let mut in_metadata = false;
+    for op in ops {
+        match &op.kind {
+            OpKind::Start(Node::Metadata) => in_metadata = true,
+            OpKind::Value if in_metadata => {
+                return match &op.content {
+                    op::Content::Span(range) => Some(range.clone()),
+                    op::Content::Materialized(_) => None,
+                };
+            }
+            OpKind::End(Node::Metadata) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Section index pages are not dated, so their `date` is optional. A missing date is
+/// appended to the text, which keeps error locations pointing into the file.
+fn parse_metadata(pid: &str, text: &str) -> Result<Metadata, serde_yaml::Error> {
+    if !pid.rsplit('/').next().is_some_and(is_section_index) {
+        return serde_yaml::from_str(text);
+    }
+    let mapping: serde_yaml::Mapping = serde_yaml::from_str(text)?;
+    if mapping.contains_key("date") {
+        return serde_yaml::from_str(text);
+    }
+    let date = DateTime::<FixedOffset>::default().to_rfc3339();
+    serde_yaml::from_str(&format!("{text}\ndate: {date}\n"))
+}
+
+/// Parses and checks metadata of a content file.
+///
+/// # Errors
+/// Returns error pointing into the file if metadata is missing, does not parse or does not
+/// match the schema and authors of `config`.
+fn page_metadata(
+    pid: &str,
+    ops: &[Op],
+    source: &str,
+    config: &Config,
+) -> Result<Metadata, BarDiagnostic> {
+    let name = format!("{}.yamd", pid.trim_start_matches('/'));
+    let range = metadata_range(ops).ok_or_else(|| MetadataSource::missing(&name, source))?;
+    let block = MetadataSource {
+        name: &name,
+        source,
+        range: range.clone(),
+    };
+    let metadata = parse_metadata(pid, &source[range]).map_err(|e| block.yaml_error(&e))?;
+    let mut errors = metadata.check_schema(&config.front_matter_schema);
+    errors.extend(metadata.check_authors(&config.authors));
+    if errors.is_empty() {
+        Ok(metadata)
+    } else {
+        Err(block.field_errors(&errors))
+    }
+}
+
+/// # Errors
+/// Returns error if content files cannot be read or parsed. Metadata errors of all files are
+/// collected into one error.
+#[allow(clippy::too_many_lines)]
+pub async fn init_pages(build_config: &BuildConfig) -> Result<Arc<Pages>, BarDiagnostic> {
+    let base_path = Arc::new(build_config.path.clone());
+    let content_path = Arc::new(
+        canonicalize_with_context(&build_config.path.join(&build_config.config.content_path))
+            .await?,
+    );
+    info!("processing YAMD from {:?}", content_path);
+    let input = get_files_by_ext_deep(&content_path, &["yamd"])
+        .await?
+        .into_iter()
+        .map(|path| (path, content_path.clone()));
+
+    let pages_vec = try_map(50, input, path_to_yamd).await?;
+    info!("processing YAMD complete");
+
+    let should_generate_alt_text = build_config
+        .config
+        .yamd_processors
+        .generate_alt_text
+        .is_some();
+
+    let alt_text_config = build_config
+        .config
+        .yamd_processors
+        .generate_alt_text
+        .as_ref()
+        .map(|config| {
+            let generator = Arc::from(Img2Text::new());
+            let config = Arc::new(config.clone());
+            (generator, config)
+        });
+
+    let mut pages = Pages::new()
+        .with_tag_registry(&build_config.config.tags)
+        .with_similarity(build_config.config.similarity);
+    let mut metadata_errors = vec![];
+
+    for (pid, source_text, ops) in pages_vec {
+        let stream: Pin<Box<dyn Stream<Item = Result<Op, BarDiagnostic>> + Send>> =
+            Box::pin(tokio_stream::iter(ops.into_iter().map(Ok)));
+
+        let stream = unwrap_cloudinary(
+            stream,
+            &source_text,
+            should_generate_alt_text,
+            base_path.clone(),
+        );
+
+        let stream = unwrap_gallery(stream, &source_text, base_path.clone());
+
+        let stream = if let Some((ref generator, ref config)) = alt_text_config {
+            add_alt_text(
+                stream,
+                &source_text,
+                generator.clone(),
+                config.clone(),
+                base_path.clone(),
+            )
+        } else {
+            stream
+        };
+
+        let ops: Vec<Op> = stream
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("processing content file: {pid}.yamd"))?;
+
+        let metadata = match page_metadata(&pid, &ops, &source_text, &build_config.config) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                metadata_errors.push(e);
+                continue;
+            }
+        };
+
+        if metadata.is_draft.unwrap_or(false) {
+            info!("skipping draft: {pid}");
+            continue;
+        }
+
+        let mut metadata = metadata;
+        let (base_pid, suffix) = build_config.config.split_language(&pid);
+        let lang = metadata
+            .lang
+            .take()
+            .or_else(|| suffix.map(Arc::from))
+            .unwrap_or_else(|| build_config.config.language.clone());
+        metadata.lang = Some(lang);
+        metadata
+            .translation_key
+            .get_or_insert_with(|| base_pid.into());
+
+        let path = build_config.config.permalink(&pid, &metadata);
+        pages.try_push(
+            Page::new(pid.into(), ops, source_text, metadata)
+                .with_path(path)
+                .with_words_per_minute(build_config.config.words_per_minute)
+                .with_excerpt_words(build_config.config.excerpt_words),
+        )?;
+    }
+
+    match metadata_errors.len() {
+        0 => {}
+        1 => return Err(metadata_errors.remove(0)),
+        count => {
+            let error = BarDiagnostic::new(format!("{count} content files have invalid metadata"));
+            return Err(metadata_errors
+                .into_iter()
+                .fold(error, BarDiagnostic::with_related));
+        }
+    }
+
+    pages.check_tag_slugs()?;
+    pages.set_section_paths(|section| build_config.config.section_path(section));
+    pages.link_pages(&build_config.config.domain);
+    pages.split_languages(|lang, section| {
+        let config = &build_config.config;
+        if lang == config.language.as_ref() {
+            config.section_path(section)
+        } else {
+            config.section_path(&format!("/{lang}{section}"))
+        }
+    });
+    pages.check_series()?;
+
+    Ok(Arc::new(pages))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use chrono::prelude::*;
+
+    use std::{collections::HashMap, sync::Arc};
+
+    use url::Url;
+
+    use crate::{
+        config::{Config, Tag},
+        context::BuildConfig,
+        metadata::{Metadata, PageKind},
+        pages::init_pages,
+    };
+
+    use super::{ContentStats, Page, PageQuery, Pages, SimilarityConfig, op, tag_slug};
+
+    #[tokio::test]
+    async fn init_from_path_test() {
+        let config_path = Path::new("./test/fixtures/").to_path_buf();
+        let build_config = BuildConfig {
+            config: Config::try_from(&config_path).unwrap(),
+            path: config_path,
+        };
+        let pages = init_pages(&build_config).await.unwrap();
+
+        assert_eq!(pages.keys().len(), 2);
+        assert_eq!(
+            pages.get("/test").unwrap().get_title(),
+            "test 1".to_string()
+        );
+        assert_eq!(
+            pages.get("/test2").unwrap().get_title(),
+            "test 2".to_string()
+        );
+        assert_eq!(pages.get_tags().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn init_pages_excludes_drafts() {
+        let config_path = Path::new("./test/fixtures/").to_path_buf();
+        let build_config = BuildConfig {
+            config: Config::try_from(&config_path).unwrap(),
+            path: config_path,
+        };
+        let pages = init_pages(&build_config).await.unwrap();
+
+        assert!(
+            pages.get("/draft").is_none(),
+            "draft yamd must be excluded from Pages, got keys: {:?}",
+            pages.keys()
+        );
+        assert!(
+            !pages
+                .get_tags()
+                .iter()
+                .any(|t| t.as_ref() == "draft only tag"),
+            "tags from a draft must not appear in get_tags(): {:?}",
+            pages.get_tags()
+        );
+    }
+
+    #[test]
+    fn get_by_path_resolves_published_path() {
+        let mut pages = Pages::new();
+        pages.push(
+            Page::new("/a".into(), vec![], String::new(), Metadata::default()).with_path("/a/"),
+        );
+        pages.push(Page::new(
+            "/b".into(),
+            vec![],
+            String::new(),
+            Metadata::default(),
+        ));
+
+        assert_eq!(pages.get_by_path("/a/").map(|p| p.pid.as_ref()), Some("/a"));
+        assert_eq!(
+            pages.get_by_path("/a/index.html").map(|p| p.pid.as_ref()),
+            Some("/a")
+        );
+        assert_eq!(
+            pages.get_by_path("/b.html").map(|p| p.pid.as_ref()),
+            Some("/b")
+        );
+        assert!(pages.get_by_path("/").is_none());
+        assert!(pages.get_by_path("/a.html").is_none());
+        assert!(pages.get_by_path("/b").is_none());
+    }
+
+    #[test]
+    fn standalone_pages_are_not_listed() {
+        let mut pages = Pages::new();
+        pages.push(Page::new(
+            "/post".into(),
+            vec![],
+            String::new(),
+            Metadata {
+                tags: Some(vec!["t1".into()]),
+                ..Metadata::default()
+            },
+        ));
+        pages.push(Page::new(
+            "/about".into(),
+            vec![],
+            String::new(),
+            Metadata {
+                tags: Some(vec!["t1".into(), "t2".into()]),
+                kind: PageKind::Page,
+                ..Metadata::default()
+            },
+        ));
+
+        assert!(pages.get("/about").is_some());
+        assert_eq!(pages.get_tags().len(), 1);
+        assert!(pages.get_similar("/post", 3).is_empty());
+        assert_eq!(similar_pids(&pages, "/about", 3), vec![Arc::from("/post")]);
+    }
+
+    #[test]
+    fn get_posts_by_author_paginates() {
+        let mut pages = Pages::new();
+        for i in 0..3 {
+            pages.push(Page::new(
+                format!("/{i}").into(),
+                vec![],
+                String::new(),
+                Metadata {
+                    date: DateTime::from_timestamp(i, 0).unwrap().into(),
+                    authors: Some(vec!["jane".into()]),
+                    ..Metadata::default()
+                },
+            ));
+        }
+
+        let slice = pages.get_posts_by_author("jane", 2, 2).expect("slice");
+        assert_eq!(slice.total_slices, 2);
+        assert_eq!(slice.current_slice, 1);
+        assert_eq!(
+            slice
+                .pages
+                .iter()
+                .map(|p| p.pid.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["/0"]
+        );
+        assert!(pages.get_posts_by_author("john", 2, 0).is_none());
+    }
+
+    fn series_page(pid: &str, order: Option<usize>) -> Page {
+        Page::new(
+            pid.into(),
+            vec![],
+            String::new(),
+            Metadata {
+                series: Some("tutorial".into()),
+                series_order: order,
+                ..Metadata::default()
+            },
+        )
+    }
+
+    #[test]
+    fn series_is_ordered_with_navigation() {
+        let mut pages = Pages::new();
+        pages.push(series_page("/part-2", Some(2)));
+        pages.push(series_page("/part-3", Some(3)));
+        pages.push(series_page("/part-1", Some(1)));
+
+        assert_eq!(
+            pages
+                .get_series("tutorial")
+                .unwrap()
+                .iter()
+                .map(|p| p.pid.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["/part-1", "/part-2", "/part-3"]
+        );
+        let nav = pages.get_series_nav("/part-2").expect("nav");
+        assert_eq!((nav.position, nav.total), (2, 3));
+        assert_eq!(nav.prev.map(|p| p.pid.clone()), Some("/part-1".into()));
+        assert_eq!(nav.next.map(|p| p.pid.clone()), Some("/part-3".into()));
+        assert!(pages.get_series_nav("/part-1").unwrap().prev.is_none());
+        assert!(pages.check_series().is_ok());
+    }
+
+    #[test]
+    fn check_series_reports_duplicate_and_missing_order() {
+        let mut pages = Pages::new();
+        pages.push(series_page("/a", Some(1)));
+        pages.push(series_page("/b", Some(1)));
+        pages.push(series_page("/c", None));
+
+        let err = pages.check_series().unwrap_err();
+        let related: Vec<String> = miette::Diagnostic::related(&err)
+            .expect("related")
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            related,
+            vec![
+                "/a and /b share series_order 1 in series 'tutorial'",
+                "/c is in series 'tutorial' but has no series_order",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_series_reports_gaps() {
+        let mut pages = Pages::new();
+        pages.push(series_page("/a", Some(1)));
+        pages.push(series_page("/b", Some(2)));
+        pages.push(series_page("/c", Some(4)));
+        pages.push(series_page("/d", Some(7)));
+
+        let err = pages.check_series().unwrap_err();
+        let related: Vec<String> = miette::Diagnostic::related(&err)
+            .expect("related")
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            related,
+            vec![
+                "series 'tutorial' skips series_order 3",
+                "series 'tutorial' skips series_order 5 to 6",
+            ]
+        );
+    }
+
+    fn dated_page(pid: &str, days_ago: i64, tags: &[&str]) -> Page {
+        Page::new(
+            pid.into(),
+            vec![],
+            String::new(),
+            Metadata {
+                date: (Utc::now() - chrono::Duration::days(days_ago)).into(),
+                tags: Some(tags.iter().map(|t| (*t).into()).collect()),
+                ..Metadata::default()
+            },
+        )
+    }
+
+    #[test]
+    fn prev_and_next_follow_date_order() {
+        let mut pages = Pages::new();
+        pages.push(dated_page("/old", 3, &["rust"]));
+        pages.push(dated_page("/mid", 2, &["go"]));
+        pages.push(dated_page("/new", 1, &["rust"]));
+
+        let pid = |page: Option<Arc<Page>>| page.map(|p| p.pid.clone());
+        assert_eq!(pid(pages.get_prev("/mid", None)), Some("/old".into()));
+        assert_eq!(pid(pages.get_next("/mid", None)), Some("/new".into()));
+        assert_eq!(pid(pages.get_prev("/old", None)), None);
+        assert_eq!(pid(pages.get_next("/new", None)), None);
+        assert_eq!(
+            pid(pages.get_next("/old", Some("rust"))),
+            Some("/new".into())
+        );
+        assert_eq!(pid(pages.get_prev("/mid", Some("rust"))), None);
+    }
+
+    fn page_at(pid: &str, date: &str) -> Page {
+        Page::new(
+            pid.into(),
+            vec![],
+            String::new(),
+            Metadata {
+                date: DateTime::parse_from_rfc3339(date).unwrap(),
+                ..Metadata::default()
+            },
+        )
     }
-    None
-}
 
-/// # Errors
-/// Returns error if content files cannot be read or parsed.
-pub async fn init_pages(build_config: &BuildConfig) -> Result<Arc<Pages>, BarDiagnostic> {
-    let base_path = Arc::new(build_config.path.clone());
-    let content_path = Arc::new(
-        canonicalize_with_context(&build_config.path.join(&build_config.config.content_path))
-            .await?,
-    );
-    info!("processing YAMD from {:?}", content_path);
-    let input = get_files_by_ext_deep(&content_path, &["yamd"])
-        .await?
-        .into_iter()
-        .map(|path| (path, content_path.clone()));
+    #[test]
+    fn archive_counts_and_periods() {
+        let mut pages = Pages::new();
+        pages.push(page_at("/a", "2023-01-10T10:00:00Z"));
+        pages.push(page_at("/b", "2023-01-20T10:00:00Z"));
+        pages.push(page_at("/c", "2023-05-01T10:00:00Z"));
+        pages.push(page_at("/d", "2024-02-01T10:00:00Z"));
 
-    let pages_vec = try_map(50, input, path_to_yamd).await?;
-    info!("processing YAMD complete");
+        let archive = pages.get_archive();
+        assert_eq!(
+            archive
+                .iter()
+                .map(|y| (
+                    y.year,
+                    y.count,
+                    y.months.iter().map(|m| (m.month, m.count)).collect()
+                ))
+                .collect::<Vec<(i32, usize, Vec<(u32, usize)>)>>(),
+            vec![(2024, 1, vec![(2, 1)]), (2023, 3, vec![(5, 1), (1, 2)])]
+        );
 
-    let should_generate_alt_text = build_config
-        .config
-        .yamd_processors
-        .generate_alt_text
-        .is_some();
+        let january = pages.get_posts_by_period(2023, Some(1), 10, 0).unwrap();
+        assert_eq!(
+            january
+                .pages
+                .iter()
+                .map(|p| p.pid.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["/b", "/a"]
+        );
+        let year = pages.get_posts_by_period(2023, None, 2, 2).unwrap();
+        assert_eq!((year.current_slice, year.total_slices), (1, 2));
+        assert_eq!(
+            year.pages
+                .iter()
+                .map(|p| p.pid.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["/a"]
+        );
+        assert!(pages.get_posts_by_period(2022, None, 10, 0).is_none());
+    }
 
-    let alt_text_config = build_config
-        .config
-        .yamd_processors
-        .generate_alt_text
-        .as_ref()
-        .map(|config| {
-            let generator = Arc::from(Img2Text::new());
-            let config = Arc::new(config.clone());
-            (generator, config)
-        });
+    fn query_page(pid: &str, date: &str, title: &str, tags: &[&str], weight: Option<i64>) -> Page {
+        let mut page = page_at(pid, date);
+        page.metadata.title = title.into();
+        page.metadata.tags = Some(tags.iter().map(|t| (*t).into()).collect());
+        if let Some(weight) = weight {
+            page.metadata.extra.insert("weight".into(), weight.into());
+        }
+        page
+    }
 
-    let mut pages = Pages::new();
+    fn query_pids(pages: &Pages, query: &PageQuery) -> Vec<String> {
+        pages
+            .query(query)
+            .iter()
+            .map(|page| page.pid.to_string())
+            .collect()
+    }
 
-    for (pid, source_text, ops) in pages_vec {
-        let stream: Pin<Box<dyn Stream<Item = Result<Op, BarDiagnostic>> + Send>> =
-            Box::pin(tokio_stream::iter(ops.into_iter().map(Ok)));
+    #[test]
+    fn query_filters_and_sorts() {
+        let mut pages = Pages::new();
+        pages.push(query_page(
+            "/a",
+            "2023-01-01T00:00:00Z",
+            "Gamma",
+            &["rust", "web"],
+            Some(2),
+        ));
+        pages.push(query_page(
+            "/b",
+            "2023-06-01T00:00:00Z",
+            "Alpha",
+            &["rust"],
+            None,
+        ));
+        pages.push(query_page(
+            "/c",
+            "2024-01-01T00:00:00Z",
+            "Beta",
+            &["go", "web"],
+            Some(1),
+        ));
 
-        let stream = unwrap_cloudinary(
-            stream,
-            &source_text,
-            should_generate_alt_text,
-            base_path.clone(),
+        assert_eq!(
+            query_pids(&pages, &PageQuery::default()),
+            ["/c", "/b", "/a"]
         );
+        let any = PageQuery {
+            any_tags: vec!["go".into(), "rust".into()],
+            exclude_tags: vec!["web".into()],
+            ..PageQuery::default()
+        };
+        assert_eq!(query_pids(&pages, &any), ["/b"]);
+        let all = PageQuery {
+            all_tags: vec!["rust".into(), "web".into()],
+            ..PageQuery::default()
+        };
+        assert_eq!(query_pids(&pages, &all), ["/a"]);
+        let range = PageQuery {
+            from: Some(DateTime::parse_from_rfc3339("2023-06-01T00:00:00Z").unwrap()),
+            to: Some(DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap()),
+            ..PageQuery::default()
+        };
+        assert_eq!(query_pids(&pages, &range), ["/b"]);
+        let field = PageQuery {
+            fields: vec![("weight".into(), 1.into())],
+            ..PageQuery::default()
+        };
+        assert_eq!(query_pids(&pages, &field), ["/c"]);
+        let by_title = PageQuery {
+            sort_by: Some("title".into()),
+            ascending: true,
+            ..PageQuery::default()
+        };
+        assert_eq!(query_pids(&pages, &by_title), ["/b", "/c", "/a"]);
+        let by_weight = PageQuery {
+            sort_by: Some("weight".into()),
+            ..PageQuery::default()
+        };
+        assert_eq!(query_pids(&pages, &by_weight), ["/a", "/c", "/b"]);
+    }
 
-        let stream = unwrap_gallery(stream, &source_text, base_path.clone());
+    #[test]
+    fn tags_are_folded_and_aliased() {
+        let mut registry = HashMap::new();
+        registry.insert(
+            "Rust".into(),
+            Tag {
+                description: Some("Systems language".into()),
+                aliases: vec!["RustLang".into()],
+                ..Tag::default()
+            },
+        );
+        let mut pages = Pages::new().with_tag_registry(&registry);
+        pages.push(dated_page("/a", 1, &["Rust", "Café Crème"]));
+        pages.push(dated_page("/b", 2, &["rustlang", "rust"]));
 
-        let stream = if let Some((ref generator, ref config)) = alt_text_config {
-            add_alt_text(
-                stream,
-                &source_text,
-                generator.clone(),
-                config.clone(),
-                base_path.clone(),
-            )
-        } else {
-            stream
-        };
+        let b = pages.get("/b").unwrap();
+        assert_eq!(b.tags, vec![Arc::<str>::from("rust")]);
+        assert_eq!(
+            b.metadata.tags,
+            Some(vec!["rustlang".into(), "rust".into()])
+        );
+        let rust = pages.get_tag("RUST").expect("rust tag");
+        assert_eq!(
+            (rust.name.as_ref(), rust.slug.as_str(), rust.count),
+            ("Rust", "rust", 2)
+        );
+        assert_eq!(rust.description.as_deref(), Some("Systems language"));
+        let cafe = pages.get_tag("café crème").expect("cafe tag");
+        assert_eq!(
+            (cafe.name.as_ref(), cafe.slug.as_str()),
+            ("Café Crème", "cafe-creme")
+        );
+        assert!(pages.get_posts_by_tag("RustLang", 10, 0).is_some());
+        assert!(pages.get_tag("go").is_none());
+    }
 
-        let ops: Vec<Op> = stream
-            .collect::<Vec<_>>()
-            .await
-            .into_iter()
-            .collect::<Result<Vec<_>, _>>()
-            .with_context(|| format!("processing content file: {pid}.yamd"))?;
+    #[test]
+    fn tag_slug_collisions_fail() {
+        let mut pages = Pages::new();
+        pages.push(dated_page("/a", 1, &["C++", "Rust"]));
+        pages.push(dated_page("/b", 2, &["Go", "rust "]));
+        assert!(pages.check_tag_slugs().is_ok());
+
+        pages.push(dated_page("/c", 3, &["C"]));
+        let err = pages.check_tag_slugs().unwrap_err();
+        let related: Vec<String> = miette::Diagnostic::related(&err)
+            .expect("related")
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(related, vec!["tags 'C', 'C++' share slug 'c'"]);
+    }
 
-        let metadata_str = extract_metadata(&ops, &source_text)
-            .ok_or_else(|| BarDiagnostic::from(format!("{pid} is missing metadata")))?;
-        let metadata: Metadata = serde_yaml::from_str(metadata_str)
-            .map_err(|e| BarDiagnostic::from(format!("{pid} has invalid yaml metadata: {e}")))?;
+    #[test]
+    fn url_slugs_are_ascii_without_stray_dashes() {
+        assert_eq!(tag_slug("Hello World!"), "hello-world");
+        assert_eq!(tag_slug(" --Mt. Blanc-- "), "mt-blanc");
+        assert_eq!(tag_slug("Straße über Köln"), "strasse-uber-koln");
+        assert_eq!(tag_slug("C++"), "c");
+    }
 
-        if metadata.is_draft.unwrap_or(false) {
-            info!("skipping draft: {pid}");
-            continue;
+    #[test]
+    fn categories_form_a_tree() {
+        let mut pages = Pages::new();
+        for (pid, categories) in [
+            ("/alps", vec!["travel/europe/alps"]),
+            ("/paris", vec![" travel / europe /", "food"]),
+            ("/tokyo", vec!["travel/asia"]),
+        ] {
+            let mut page = dated_page(pid, 1, &[]);
+            page.metadata.categories = Some(categories.into_iter().map(Into::into).collect());
+            pages.push(page);
         }
 
-        pages.add(&pid, ops, source_text, metadata);
+        assert_eq!(
+            pages.get("/paris").unwrap().metadata.categories,
+            Some(vec!["travel/europe".into(), "food".into()])
+        );
+        let travel = pages.get_posts_by_category("travel", 10, 0).unwrap();
+        assert_eq!(travel.pages.len(), 3);
+        let europe = pages
+            .get_posts_by_category("travel/europe/", 10, 0)
+            .unwrap();
+        assert_eq!(europe.pages.len(), 2);
+
+        let tree = pages.get_category_tree(None);
+        let names = |nodes: &[super::CategoryNode]| -> Vec<(String, usize)> {
+            nodes
+                .iter()
+                .map(|node| (node.crumb.path.to_string(), node.count))
+                .collect()
+        };
+        assert_eq!(names(&tree), [("food".into(), 1), ("travel".into(), 3)]);
+        assert_eq!(
+            names(&tree[1].children),
+            [("travel/asia".into(), 1), ("travel/europe".into(), 2)]
+        );
+        assert_eq!(
+            names(&pages.get_category_tree(Some("travel/europe"))),
+            [("travel/europe/alps".into(), 1)]
+        );
+
+        let crumbs = pages
+            .get_category_breadcrumbs("travel/europe/alps")
+            .unwrap();
+        assert_eq!(
+            crumbs
+                .iter()
+                .map(|crumb| (crumb.name.as_ref(), crumb.path.as_ref()))
+                .collect::<Vec<_>>(),
+            [
+                ("travel", "travel"),
+                ("europe", "travel/europe"),
+                ("alps", "travel/europe/alps")
+            ]
+        );
+        assert!(pages.get_category_breadcrumbs("travel/africa").is_none());
     }
 
-    Ok(Arc::new(pages))
-}
+    #[test]
+    fn sections_follow_directories() {
+        let mut pages = Pages::new();
+        pages.push(dated_page("/about", 1, &[]));
+        pages.push(dated_page("/travel/europe/alps", 1, &[]));
+        pages.push(dated_page("/travel/europe/paris", 2, &[]));
+        let mut index = dated_page("/travel/_index", 0, &[]);
+        index.metadata.title = "Travel notes".into();
+        pages.push(index);
+        pages.set_section_paths(|section| format!("{section}.html"));
 
-#[cfg(test)]
-mod test {
-    use std::path::Path;
+        assert_eq!(
+            pages.section_keys(),
+            ["/", "/travel", "/travel/europe"].map(Arc::from)
+        );
+        let travel = pages.get_section("travel/").expect("travel");
+        assert_eq!(travel.title.as_ref(), "Travel notes");
+        assert_eq!(travel.count, 0);
+        assert_eq!(travel.subsections, vec![Arc::from("/travel/europe")]);
+        assert!(!travel.index.expect("index").metadata.is_article());
+        assert!(
+            pages
+                .get_articles(None)
+                .unwrap()
+                .iter()
+                .all(|p| p.pid.as_ref() != "/travel/_index")
+        );
 
-    use chrono::prelude::*;
+        let europe = pages
+            .get_section_by_path("/travel/europe.html")
+            .expect("europe");
+        assert_eq!((europe.title.as_ref(), europe.count), ("europe", 2));
+        let listed = pages.get_posts_by_section("/travel/europe", 10, 0).unwrap();
+        assert_eq!(
+            listed
+                .pages
+                .iter()
+                .map(|p| p.pid.as_ref())
+                .collect::<Vec<_>>(),
+            ["/travel/europe/alps", "/travel/europe/paris"]
+        );
+        assert_eq!(
+            pages.get_section("/").unwrap().subsections,
+            vec![Arc::from("/travel")]
+        );
+    }
 
-    use crate::{config::Config, context::BuildConfig, metadata::Metadata, pages::init_pages};
+    #[test]
+    fn section_index_date_is_optional() {
+        assert!(super::parse_metadata("/travel/_index", "title: Travel").is_ok());
+        assert!(super::parse_metadata("/travel/post", "title: Travel").is_err());
+    }
 
-    use super::{Page, Pages};
+    #[test]
+    fn section_index_metadata_errors_have_location() {
+        let err =
+            super::parse_metadata("/travel/_index", "title: Travel\ntags: rust\n").unwrap_err();
+        let location = err.location().expect("location");
+        assert_eq!((location.line(), location.column()), (2, 7));
+    }
 
-    #[tokio::test]
-    async fn init_from_path_test() {
-        let config_path = Path::new("./test/fixtures/").to_path_buf();
-        let build_config = BuildConfig {
-            config: Config::try_from(&config_path).unwrap(),
-            path: config_path,
-        };
-        let pages = init_pages(&build_config).await.unwrap();
+    #[test]
+    fn try_push_rejects_colliding_paths() {
+        let mut pages = Pages::new();
+        pages
+            .try_push(dated_page("/a", 1, &[]).with_path("/hello.html"))
+            .unwrap();
+        let err = pages
+            .try_push(dated_page("/b", 1, &[]).with_path("/hello.html"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "/a and /b are both published at /hello.html"
+        );
+        assert!(pages.get("/b").is_none());
+        assert!(
+            pages
+                .try_push(dated_page("/c", 1, &[]).with_path("/c.html"))
+                .is_ok()
+        );
+    }
 
-        assert_eq!(pages.keys().len(), 2);
+    fn translated_page(pid: &str, lang: &str, key: &str) -> Page {
+        let mut page = dated_page(pid, 1, &["news"]).with_path(format!("{pid}.html"));
+        page.metadata.lang = Some(lang.into());
+        page.metadata.translation_key = Some(key.into());
+        page
+    }
+
+    #[test]
+    fn languages_have_own_views_and_translations() {
+        let mut pages = Pages::new();
+        pages.push(translated_page("/post", "en", "/post"));
+        pages.push(translated_page("/post.de", "de", "/post"));
+        pages.push(translated_page("/other", "en", "/other"));
+        pages.split_languages(|lang, section| format!("/{lang}{section}"));
+
+        assert_eq!(pages.languages(), ["de", "en"].map(Arc::from));
+        let de = pages.in_language(Some("de")).expect("de view");
+        assert_eq!(de.get_posts_by_tag("news", 10, 0).unwrap().pages.len(), 1);
         assert_eq!(
-            pages.get("/test").unwrap().get_title(),
-            "test 1".to_string()
+            pages.get_posts_by_tag("news", 10, 0).unwrap().pages.len(),
+            3
         );
+        assert!(pages.in_language(Some("fr")).is_none());
+
         assert_eq!(
-            pages.get("/test2").unwrap().get_title(),
-            "test 2".to_string()
+            pages.get_translation("/post", "de").map(|p| p.pid.clone()),
+            Some("/post.de".into())
         );
-        assert_eq!(pages.get_tags().len(), 4);
+        let base_url = Url::parse("https://example.com").unwrap();
+        let alternates = serde_json::to_value(pages.get_alternates("/post", &base_url)).unwrap();
+        assert_eq!(
+            alternates,
+            serde_json::json!([
+                {"lang": "de", "path": "/post.de.html", "url": "https://example.com/post.de.html"},
+                {"lang": "en", "path": "/post.html", "url": "https://example.com/post.html"}
+            ])
+        );
+        assert!(pages.get_alternates("/other", &base_url).is_empty());
     }
 
-    #[tokio::test]
-    async fn init_pages_excludes_drafts() {
-        let config_path = Path::new("./test/fixtures/").to_path_buf();
-        let build_config = BuildConfig {
-            config: Config::try_from(&config_path).unwrap(),
-            path: config_path,
-        };
-        let pages = init_pages(&build_config).await.unwrap();
+    #[test]
+    fn translated_series_are_checked_per_language() {
+        let mut pages = Pages::new();
+        for (pid, lang, order) in [
+            ("/part-1", "en", 1),
+            ("/part-2", "en", 2),
+            ("/part-1.de", "de", 1),
+            ("/part-2.de", "de", 2),
+        ] {
+            let mut page = series_page(pid, Some(order));
+            page.metadata.lang = Some(lang.into());
+            pages.push(page);
+        }
+        pages.split_languages(|_, section| section.to_string());
 
-        assert!(
-            pages.get("/draft").is_none(),
-            "draft yamd must be excluded from Pages, got keys: {:?}",
-            pages.keys()
-        );
-        assert!(
-            !pages
-                .get_tags()
+        assert!(pages.check_series().is_ok());
+        let de = pages.in_language(Some("de")).expect("de view");
+        let series = de.get_series("tutorial").unwrap();
+        assert_eq!(
+            series
                 .iter()
-                .any(|t| t.as_ref() == "draft only tag"),
-            "tags from a draft must not appear in get_tags(): {:?}",
-            pages.get_tags()
+                .map(|page| page.pid.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["/part-1.de", "/part-2.de"]
+        );
+        let nav = de.get_series_nav("/part-1.de").expect("nav");
+        assert_eq!(
+            nav.next.map(|page| page.pid.clone()),
+            Some("/part-2.de".into())
         );
     }
 
+    #[test]
+    fn single_language_has_no_views() {
+        let mut pages = Pages::new();
+        pages.push(translated_page("/post", "en", "/post"));
+        pages.split_languages(|_, section| section.to_string());
+        assert!(pages.languages().is_empty());
+        assert!(pages.in_language(Some("en")).is_some());
+    }
+
     #[test]
     fn get_similar() {
         let mut pages = Pages::new();
@@ -439,6 +2347,7 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t1".into(), "t2".into(), "t3".into(), "t4".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         ));
         pages.push(Page::new(
@@ -452,6 +2361,7 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t1".into(), "t7".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         ));
         pages.push(Page::new(
@@ -465,6 +2375,7 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t2".into(), "t3".into(), "t4".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         ));
         pages.push(Page::new(
@@ -478,6 +2389,7 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t5".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         ));
         pages.push(Page::new(
@@ -497,6 +2409,7 @@ mod test {
                     "t5".into(),
                 ]),
                 is_draft: None,
+                ..Metadata::default()
             },
         ));
         pages.push(Page::new(
@@ -510,13 +2423,92 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t1".into(), "t3".into(), "t5".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         ));
 
         assert_eq!(
-            pages.get_similar("1", 3),
-            vec!["5".into(), "3".into(), "6".into()]
+            similar_pids(&pages, "1", 3),
+            vec!["3".into(), "5".into(), "6".into()]
+        );
+    }
+
+    fn similar_pids(pages: &Pages, pid: &str, max: usize) -> Vec<Arc<str>> {
+        pages
+            .get_similar(pid, max)
+            .into_iter()
+            .map(|similar| similar.pid)
+            .collect()
+    }
+
+    #[test]
+    fn get_similar_prefers_rare_tags() {
+        let mut pages = Pages::new();
+        pages.push(dated_page("/source", 5, &["blog", "wasm"]));
+        pages.push(dated_page("/common", 1, &["blog"]));
+        pages.push(dated_page("/rare", 2, &["wasm"]));
+        pages.push(dated_page("/other", 3, &["blog"]));
+        pages.push(dated_page("/another", 4, &["blog"]));
+
+        let similar = pages.get_similar("/source", 2);
+        assert_eq!(similar[0].pid.as_ref(), "/rare");
+        assert_eq!(similar[1].pid.as_ref(), "/common");
+        assert!(similar[0].score > similar[1].score);
+        assert!(similar[0].score <= 1.0);
+    }
+
+    #[test]
+    fn get_similar_breaks_ties_by_page_order() {
+        let mut pages = Pages::new();
+        pages.push(dated_page("/source", 4, &["rust"]));
+        pages.push(dated_page("/b", 1, &["rust"]));
+        pages.push(dated_page("/a", 1, &["rust"]));
+        pages.push(dated_page("/old", 3, &["rust"]));
+
+        for _ in 0..10 {
+            assert_eq!(
+                similar_pids(&pages, "/source", 3),
+                vec![Arc::from("/a"), Arc::from("/b"), Arc::from("/old")]
+            );
+        }
+    }
+
+    /// Dated page with `body` as content, with ops, source and links as parsed from a file.
+    fn text_page(pid: &str, days_ago: i64, body: &str) -> Page {
+        let source = format!("---\ntitle: {pid}\n---\n\n{body}");
+        let metadata = dated_page(pid, days_ago, &[]).metadata;
+        Page::new(pid.into(), op::parse(&source), source, metadata)
+    }
+
+    #[test]
+    fn get_similar_compares_content() {
+        let weights = SimilarityConfig {
+            tags: 1.0,
+            content: 1.0,
+        };
+        let mut pages = Pages::new().with_similarity(weights);
+        pages.push(text_page(
+            "/borrow",
+            3,
+            "The borrow checker rejects aliasing references.",
+        ));
+        pages.push(text_page(
+            "/lifetimes",
+            2,
+            "Lifetimes tell the borrow checker how long references live.",
+        ));
+        pages.push(text_page("/bread", 1, "Knead dough, then let it rise."));
+
+        assert_eq!(
+            similar_pids(&pages, "/borrow", 3),
+            vec![Arc::from("/lifetimes")]
         );
+
+        // content is ignored by default
+        let mut tags_only = Pages::new();
+        tags_only.push(text_page("/borrow", 3, "borrow checker"));
+        tags_only.push(text_page("/lifetimes", 2, "borrow checker"));
+        assert!(tags_only.get_similar("/borrow", 3).is_empty());
     }
 
     #[test]
@@ -532,6 +2524,7 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t1".into(), "t2".into(), "t3".into(), "t4".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         );
         let two = Page::new(
@@ -545,6 +2538,7 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t1".into(), "t2".into(), "t3".into(), "t4".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         );
 
@@ -566,6 +2560,7 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t1".into(), "t2".into(), "t3".into(), "t4".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         );
         let two = Page::new(
@@ -579,9 +2574,71 @@ mod test {
                 preview: None,
                 tags: Some(vec!["t1".into(), "t2".into(), "t3".into(), "t4".into()]),
                 is_draft: None,
+                ..Metadata::default()
             },
         );
 
         assert!(one < two);
     }
+
+    #[test]
+    fn content_stats_count_prose_and_code_separately() {
+        let source = "---\ntitle: t\n---\n\n# Hello world\n\nSome **bold** text with [a link](https://example.com/long/url).\n\n```rust\nfn main() {}\n```\n";
+        let ops = op::parse(source);
+        let stats = ContentStats::new(&ops, source, 200);
+        assert_eq!(stats.word_count, 8, "{stats:?}");
+        assert_eq!(stats.character_count, 32, "{stats:?}");
+        assert_eq!(stats.code_word_count, 3, "{stats:?}");
+        assert_eq!(stats.reading_time, 1);
+    }
+
+    #[test]
+    fn reading_time_uses_words_per_minute() {
+        let source = "word ".repeat(450);
+        let ops = op::parse(&source);
+        let stats = ContentStats::new(&ops, &source, 200);
+        assert_eq!(stats.word_count, 450);
+        assert_eq!(stats.reading_time, 3);
+        assert_eq!(stats.with_words_per_minute(500).reading_time, 1);
+        assert_eq!(ContentStats::default().reading_time, 0);
+    }
+
+    #[test]
+    fn backlinks_resolve_internal_links() {
+        let domain = Url::parse("https://example.com").unwrap();
+        let mut pages = Pages::new();
+        pages.push(text_page("/posts/alps", 3, "Nothing here."));
+        pages.push(text_page(
+            "/posts/day-1",
+            2,
+            "See [the Alps](/posts/alps.html#top) and [myself](day-1.html).",
+        ));
+        pages.push(text_page(
+            "/posts/day-2",
+            1,
+            "[Alps again](alps.html), [twice](https://example.com/posts/alps.html), [elsewhere](https://other.com/posts/alps.html).",
+        ));
+        pages.link_pages(&domain);
+
+        let backlinks = pages
+            .get_backlinks("/posts/alps")
+            .iter()
+            .map(|link| (link.page.pid.as_ref(), link.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            backlinks,
+            vec![("/posts/day-2", "Alps again"), ("/posts/day-1", "the Alps")]
+        );
+        assert!(pages.get_backlinks("/posts/day-1").is_empty());
+
+        let graph = serde_json::to_value(pages.get_link_graph()).unwrap();
+        assert_eq!(graph["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            graph["edges"],
+            serde_json::json!([
+                {"source": "/posts/day-1", "target": "/posts/alps", "text": "the Alps"},
+                {"source": "/posts/day-2", "target": "/posts/alps", "text": "Alps again"},
+            ])
+        );
+    }
 }
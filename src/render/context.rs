@@ -6,9 +6,10 @@ use syntect::util::LinesWithEndings;
 use yamd::op::{Content, Node, Op, OpKind};
 
 use crate::diagnostic::BarDiagnostic;
+use crate::pages::parse_pid_link;
 
 use super::engine::{FragmentEngine, find_matching_end, fragment_template_name};
-use super::{RenderCtx, render_node, render_ops_to_html};
+use super::{HeadingLink, RenderCtx, render_node, render_ops_to_html};
 
 pub(super) fn resolve_content<'a>(content: &'a Content, source: &'a str) -> &'a str {
     match content {
@@ -121,6 +122,7 @@ pub(super) fn build_fragment_context(
         theme,
         syntax_set,
         engine,
+        anchors,
         ..
     } = render_ctx;
     let has_services = engine.has_services;
@@ -227,7 +229,11 @@ pub(super) fn build_fragment_context(
                 }
             }
 
-            let slug = slugify(slug_text.trim());
+            let text = slug_text.trim();
+            let slug = anchors.borrow_mut().unique_id(&slugify(text));
+            anchors
+                .borrow_mut()
+                .add_heading(level, text.to_string(), slug.clone());
             ctx.insert("level", &level);
             ctx.insert("body", &body_html);
             ctx.insert("text", &slug_text);
@@ -237,6 +243,7 @@ pub(super) fn build_fragment_context(
         Node::Anchor => {
             let mut title = String::new();
             let mut dest = String::new();
+            let mut dest_span = None;
             let mut in_title = false;
             let mut in_dest = false;
             for op in &ops[start + 1..end] {
@@ -251,12 +258,53 @@ pub(super) fn build_fragment_context(
                             title.push_str(text);
                         } else if in_dest {
                             dest.push_str(text);
+                            if let Content::Span(range) = &op.content {
+                                dest_span = Some((range.start, range.len()));
+                            }
                         }
                     }
                     _ => {}
                 }
             }
-            ctx.insert("href", &dest);
+            let locate = |diag: BarDiagnostic, label: &str| match dest_span {
+                Some(span) => diag
+                    .with_source_code(render_ctx.source_name.to_string(), source.to_string())
+                    .with_label(span.into(), label.to_string()),
+                None => diag,
+            };
+            let href = match parse_pid_link(&dest) {
+                Some((pid, fragment)) => {
+                    let target = engine
+                        .pages
+                        .as_deref()
+                        .and_then(|pages| pages.get(&pid))
+                        .ok_or_else(|| {
+                            locate(
+                                BarDiagnostic::new(format!("link to unknown page '{pid}'")).with_help(
+                                    "`pid:` links take the content file path without `.yamd`, e.g. `pid:/travel/alps`",
+                                ),
+                                "no page with this pid",
+                            )
+                        })?;
+                    if title.trim().is_empty() {
+                        title = target.get_title();
+                    }
+                    match fragment {
+                        Some(fragment) => {
+                            // checked against the table of contents once the target is rendered
+                            anchors.borrow_mut().add_heading_link(HeadingLink {
+                                pid: target.pid.clone(),
+                                fragment: fragment.to_string(),
+                                span: dest_span,
+                            });
+                            format!("{}#{fragment}", target.path)
+                        }
+                        None => target.path.to_string(),
+                    }
+                }
+                None => dest,
+            };
+            ctx.insert("href", &href);
             ctx.insert("text", &title);
         }
         Node::Embed => {
@@ -302,11 +350,12 @@ pub(super) fn build_fragment_context(
             }
 
             let slug = slugify(&title);
-            let id = if slug.chars().any(char::is_alphanumeric) {
+            let slug = if slug.chars().any(char::is_alphanumeric) {
                 slug
             } else {
                 format!("collapsible-{start}")
             };
+            let id = anchors.borrow_mut().unique_id(&slug);
 
             let toggle_icon = render_icon(engine, "play")?;
 
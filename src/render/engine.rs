@@ -201,6 +201,8 @@ pub struct FragmentEngine {
     pub(super) tera: tera::Tera,
     pub(super) css: HashMap<String, String>,
     pub(super) has_services: bool,
+    /// Content pages for `pid:` links, available with services.
+    pub(super) pages: Option<std::sync::Arc<crate::pages::Pages>>,
 }
 
 impl FragmentEngine {
@@ -274,6 +276,7 @@ impl FragmentEngine {
             tera,
             css,
             has_services: services.is_some(),
+            pages: services.map(|svc| svc.pages.clone()),
         })
     }
 }
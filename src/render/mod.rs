@@ -1,10 +1,15 @@
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::ops::Range;
 use std::sync::{Arc, Mutex};
 
+use serde::Serialize;
 use syntect::parsing::SyntaxSet;
 use yamd::op::{Node, Op, OpKind};
 
 use crate::diagnostic::BarDiagnostic;
+use crate::excerpt::is_excerpt_marker;
 use crate::theme::Theme;
 
 mod context;
@@ -21,6 +26,97 @@ pub type RenderedContentCache = Arc<Mutex<HashMap<Arc<str>, RenderedContent>>>;
 pub struct RenderedContent {
     pub html: String,
     pub css: String,
+    pub toc: Vec<TocEntry>,
+    /// Rendered excerpt of the page, see [`render_ranges_html`].
+    pub excerpt_html: String,
+    /// `pid:` links to headings, see [`check_heading_links`].
+    pub heading_links: Vec<HeadingLink>,
+}
+
+impl RenderedContent {
+    /// Anchor ids of the table of contents, in document order.
+    #[must_use]
+    pub fn anchor_ids(&self) -> Vec<String> {
+        fn collect(toc: &[TocEntry], ids: &mut Vec<String>) {
+            for entry in toc {
+                ids.push(entry.id.clone());
+                collect(&entry.children, ids);
+            }
+        }
+        let mut ids = vec![];
+        collect(&self.toc, &mut ids);
+        ids
+    }
+}
+
+/// `pid:` link to a heading of a content page.
+#[derive(Debug, Clone)]
+pub struct HeadingLink {
+    pub pid: Arc<str>,
+    pub fragment: String,
+    /// Offset and length of the link destination in the source.
+    pub span: Option<(usize, usize)>,
+}
+
+/// Heading of a page with the headings nested under it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    /// Anchor id of the heading, unique within the page.
+    pub id: String,
+    pub children: Vec<TocEntry>,
+}
+
+/// Anchor ids handed out on the page so far, the headings in document order and the `pid:`
+/// links to headings of other pages.
+#[derive(Default)]
+pub(super) struct Anchors {
+    ids: HashSet<String>,
+    headings: Vec<(u8, String, String)>,
+    heading_links: Vec<HeadingLink>,
+}
+
+impl Anchors {
+    /// Returns `slug`, or `slug-1`, `slug-2`, ... if it is already used on the page.
+    pub(super) fn unique_id(&mut self, slug: &str) -> String {
+        let mut id = slug.to_string();
+        let mut n = 0;
+        while self.ids.contains(&id) {
+            n += 1;
+            id = format!("{slug}-{n}");
+        }
+        self.ids.insert(id.clone());
+        id
+    }
+
+    pub(super) fn add_heading(&mut self, level: u8, text: String, id: String) {
+        self.headings.push((level, text, id));
+    }
+
+    pub(super) fn add_heading_link(&mut self, link: HeadingLink) {
+        self.heading_links.push(link);
+    }
+}
+
+fn nest_headings(headings: &[(u8, String, String)]) -> Vec<TocEntry> {
+    let mut toc = vec![];
+    let mut i = 0;
+    while i < headings.len() {
+        let (level, text, id) = &headings[i];
+        let end = headings[i + 1..]
+            .iter()
+            .position(|(next, _, _)| next <= level)
+            .map_or(headings.len(), |offset| i + 1 + offset);
+        toc.push(TocEntry {
+            level: *level,
+            text: text.clone(),
+            id: id.clone(),
+            children: nest_headings(&headings[i + 1..end]),
+        });
+        i = end;
+    }
+    toc
 }
 
 #[derive(Clone, Copy)]
@@ -29,6 +125,7 @@ pub(super) struct RenderCtx<'a> {
     pub(super) syntax_set: &'a SyntaxSet,
     pub(super) engine: &'a FragmentEngine,
     pub(super) source_name: &'a str,
+    pub(super) anchors: &'a RefCell<Anchors>,
 }
 
 pub(super) fn render_node(
@@ -45,7 +142,10 @@ pub(super) fn render_node(
     let end = find_matching_end(ops, start, key);
 
     let wrap_with_yamd_context = |e: BarDiagnostic| -> BarDiagnostic {
-        if let Some((offset, length)) = source_span_for_ops(ops, start, end) {
+        if e.has_labels() {
+            // already points at the yamd source
+            e
+        } else if let Some((offset, length)) = source_span_for_ops(ops, start, end) {
             BarDiagnostic::new(format!("error rendering '{key}' fragment"))
                 .with_source_code(source_name.to_string(), source.to_string())
                 .with_label(
@@ -101,6 +201,10 @@ fn walk_ops(
                     i = end + 1;
                     continue;
                 }
+                Node::Paragraph if is_excerpt_marker(ops, source, i) => {
+                    i += 3;
+                    continue;
+                }
                 _ => {
                     let (rendered, next_i) =
                         render_node(ops, source, node, i, used_nodes, render_ctx)?;
@@ -146,21 +250,99 @@ pub fn render_html(
     syntax_set: &SyntaxSet,
     source_name: &str,
 ) -> Result<RenderedContent, BarDiagnostic> {
+    let anchors = RefCell::new(Anchors::default());
     let render_ctx = RenderCtx {
         theme,
         syntax_set,
         engine,
         source_name,
+        anchors: &anchors,
     };
     let mut used_nodes: HashSet<&'static str> = HashSet::new();
     let mut html = String::with_capacity(source.len() * 2);
     walk_ops(ops, source, render_ctx, &mut html, &mut used_nodes)?;
     let css = collect_css(engine, &used_nodes);
-    Ok(RenderedContent { html, css })
+    let anchors = anchors.into_inner();
+    Ok(RenderedContent {
+        html,
+        css,
+        toc: nest_headings(&anchors.headings),
+        excerpt_html: String::new(),
+        heading_links: anchors.heading_links,
+    })
+}
+
+/// Checks that every `pid:` link of a rendered page points to a heading of its target.
+/// `anchor_ids` has the [`RenderedContent::anchor_ids`] of every content page by pid.
+///
+/// # Errors
+/// Returns an error labelled in `source` for the first link to a missing heading.
+pub fn check_heading_links<S: BuildHasher>(
+    links: &[HeadingLink],
+    source: &str,
+    source_name: &str,
+    anchor_ids: &HashMap<Arc<str>, Vec<String>, S>,
+) -> Result<(), BarDiagnostic> {
+    for HeadingLink {
+        pid,
+        fragment,
+        span,
+    } in links
+    {
+        let ids = anchor_ids.get(pid).map(Vec::as_slice).unwrap_or_default();
+        if ids.contains(fragment) {
+            continue;
+        }
+        let mut diag = BarDiagnostic::new(format!("page '{pid}' has no heading '#{fragment}'"))
+            .with_help(format!("anchors of '{pid}': {}", ids.join(", ")));
+        if let Some(span) = span {
+            diag = diag
+                .with_source_code(source_name.to_string(), source.to_string())
+                .with_label((*span).into(), "no such heading on the page");
+        }
+        return Err(diag);
+    }
+    Ok(())
+}
+
+/// Render only the top level nodes in `ranges`, e.g. the excerpt of a page.
+///
+/// # Errors
+/// Returns an error if any fragment template fails to render against its yamd input.
+pub fn render_ranges_html(
+    ops: &[Op],
+    source: &str,
+    ranges: &[Range<usize>],
+    engine: &FragmentEngine,
+    theme: &Theme,
+    syntax_set: &SyntaxSet,
+    source_name: &str,
+) -> Result<String, BarDiagnostic> {
+    let anchors = RefCell::new(Anchors::default());
+    let render_ctx = RenderCtx {
+        theme,
+        syntax_set,
+        engine,
+        source_name,
+        anchors: &anchors,
+    };
+    let mut used_nodes: HashSet<&'static str> = HashSet::new();
+    let mut html = String::new();
+    for range in ranges {
+        walk_ops(
+            &ops[range.clone()],
+            source,
+            render_ctx,
+            &mut html,
+            &mut used_nodes,
+        )?;
+    }
+    Ok(html)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::path::Path;
     use std::sync::Arc;
 
@@ -170,7 +352,9 @@ mod tests {
     use crate::syntax_highlight::init;
     use crate::theme::Theme;
 
-    use super::{FragmentEngine, RenderedContent, render_html};
+    use super::{
+        FragmentEngine, RenderedContent, check_heading_links, render_html, render_ranges_html,
+    };
 
     const TEST_THEME_TOML: &str = r#"
 [theme]
@@ -217,8 +401,8 @@ heading_anchors = true
         assert!(html.contains("<h1>"), "got: {html}");
         assert!(html.contains("</h1>"), "got: {html}");
         assert!(
-            html.contains(r##"<a id="My Title" href="#My Title" class="second">#</a>"##),
-            "heading should contain a clickable hash anchor with literal text id, got: {html}"
+            html.contains(r##"<a id="my-title" href="#my-title" class="second">#</a>"##),
+            "heading should contain a clickable hash anchor with slug id, got: {html}"
         );
         assert!(html.contains(">My Title</h1>"), "got: {html}");
     }
@@ -237,12 +421,141 @@ heading_anchors = true
         );
         assert!(
             html.contains(
-                r##"<a id="intro link end" href="#intro link end" class="second">#</a>"##
+                r##"<a id="intro-link-end" href="#intro-link-end" class="second">#</a>"##
             ),
-            "id and href should be the literal heading text (anchor label included), got: {html}"
+            "id and href should be the heading slug (anchor label included), got: {html}"
+        );
+    }
+
+    fn render_with_pages(source: &str) -> Result<String, crate::diagnostic::BarDiagnostic> {
+        let target = "---\ntitle: Alps\n---\n\n# Day 1\n\n# Day 2\n\n# Day 2";
+        let mut pages = crate::pages::Pages::new();
+        pages.push(
+            crate::pages::Page::new(
+                "/travel/alps".into(),
+                op::parse(target),
+                target.to_string(),
+                crate::metadata::Metadata {
+                    title: "Alps".into(),
+                    ..crate::metadata::Metadata::default()
+                },
+            )
+            .with_path("/2023/alps.html"),
+        );
+        let theme = test_theme();
+        let ss = test_syntax_set();
+        let mut engine = FragmentEngine::build(Path::new("/tmp"), &theme, None).expect("engine");
+        engine.pages = Some(Arc::new(pages));
+        let target = render_html(
+            &op::parse(target),
+            target,
+            &engine,
+            &theme,
+            &ss,
+            "alps.yamd",
+        )?;
+        let anchor_ids = HashMap::from([(Arc::from("/travel/alps"), target.anchor_ids())]);
+        let rendered = render_html(
+            &op::parse(source),
+            source,
+            &engine,
+            &theme,
+            &ss,
+            "test.yamd",
+        )?;
+        check_heading_links(&rendered.heading_links, source, "test.yamd", &anchor_ids)?;
+        Ok(rendered.html)
+    }
+
+    #[test]
+    fn resolves_pid_links() {
+        let html = render_with_pages("[](pid:/travel/alps) [day two](pid:travel/alps#day-2-1)")
+            .expect("render should succeed");
+        assert!(
+            html.contains(r#"<a href="/2023/alps.html">Alps</a>"#),
+            "got: {html}"
+        );
+        assert!(
+            html.contains(r#"<a href="/2023/alps.html#day-2-1">day two</a>"#),
+            "got: {html}"
         );
     }
 
+    #[test]
+    fn unknown_pid_link_is_an_error() {
+        let err = render_with_pages("see [it](pid:/travel/nowhere)").unwrap_err();
+        let msg = format!("{err:?}");
+        assert!(msg.contains("test.yamd"), "got: {msg}");
+        assert!(msg.contains("unknown page '/travel/nowhere'"), "got: {msg}");
+        assert!(msg.contains("no page with this pid"), "got: {msg}");
+    }
+
+    #[test]
+    fn unknown_pid_link_heading_is_an_error() {
+        let err = render_with_pages("see [it](pid:/travel/alps#day-3)").unwrap_err();
+        let msg = format!("{err:?}");
+        assert!(msg.contains("no heading '#day-3'"), "got: {msg}");
+        assert!(msg.contains("day-1, day-2, day-2-1"), "got: {msg}");
+        assert!(msg.contains("no such heading on the page"), "got: {msg}");
+    }
+
+    #[test]
+    fn excerpt_marker_is_not_rendered() {
+        let html = render("before\n\n<!-- more -->\n\nafter");
+        assert!(html.contains("<p>before</p>"), "got: {html}");
+        assert!(html.contains("<p>after</p>"), "got: {html}");
+        assert!(!html.contains("more"), "got: {html}");
+    }
+
+    #[test]
+    fn renders_excerpt_ranges() {
+        let source = "---\ntitle: t\n---\n\n# Head\n\nfirst\n\nsecond";
+        let ops = op::parse(source);
+        let excerpt = crate::excerpt::Excerpt::new(&ops, source, 1);
+        let theme = test_theme();
+        let ss = test_syntax_set();
+        let engine = FragmentEngine::build(Path::new("/tmp"), &theme, None).expect("engine");
+        let html = render_ranges_html(&ops, source, &excerpt.ranges, &engine, &theme, &ss, "test")
+            .expect("render should succeed");
+        assert_eq!(html, "<p>first</p>");
+    }
+
+    #[test]
+    fn duplicate_headings_get_unique_ids() {
+        let html = render("# Intro\n\n# Intro\n\n# Intro-1");
+        assert!(html.contains(r#"<a id="intro" "#), "got: {html}");
+        assert!(html.contains(r#"<a id="intro-1" "#), "got: {html}");
+        assert!(html.contains(r#"<a id="intro-1-1" "#), "got: {html}");
+    }
+
+    #[test]
+    fn collects_nested_toc() {
+        let rendered = render_full("# Intro\n\n## Setup\n\n### Details\n\n## Setup\n\n# End");
+        let toc = rendered
+            .toc
+            .iter()
+            .map(|entry| {
+                (
+                    entry.id.as_str(),
+                    entry
+                        .children
+                        .iter()
+                        .map(|child| (child.id.as_str(), child.children.len()))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            toc,
+            vec![
+                ("intro", vec![("setup", 1), ("setup-1", 0)]),
+                ("end", vec![]),
+            ]
+        );
+        assert_eq!(rendered.toc[0].children[0].children[0].level, 3);
+        assert_eq!(rendered.toc[0].children[0].children[0].text, "Details");
+    }
+
     #[test]
     fn renders_heading_with_leading_anchor() {
         let html = render("# [link](/x) tail");
@@ -1,7 +1,10 @@
+use std::collections::{BTreeSet, HashMap};
 use std::path::Path;
 use std::sync::Arc;
 
+use itertools::Itertools;
 use rss::{ChannelBuilder, Item};
+use syntect::parsing::SyntaxSet;
 use tera::{Context, Tera};
 use tracing::{debug, info};
 
@@ -10,8 +13,13 @@ use crate::{
     diagnostic::BarDiagnostic,
     fragment_services::FragmentServices,
     json_feed::{FeedItem, JsonFeedBuilder},
-    render::{FragmentEngine, render_html},
-    site::FeedType,
+    pages::PagesSlice,
+    render::{
+        FragmentEngine, RenderedContent, TocEntry, check_heading_links, render_html,
+        render_ranges_html,
+    },
+    site::{DataRecord, FeedType, Page},
+    theme::Theme,
 };
 
 fn yamd_display_path(project_path: &Path, content_path: &Path, pid: &str) -> String {
@@ -59,6 +67,8 @@ pub fn render(
         syntax_set: ctx.syntax_set.clone(),
         rendered_cache: rendered_cache.clone(),
         image_widths: Arc::new(ctx.theme.render.image.widths()),
+        i18n: ctx.i18n.clone(),
+        data: ctx.data.clone(),
     };
 
     let engine = FragmentEngine::build(&template_dir, &ctx.theme, Some(&services))?;
@@ -67,6 +77,8 @@ pub fn render(
         if let Some(content_page) = pages.get(&pid) {
             let display_path =
                 yamd_display_path(&ctx.config.path, &ctx.config.config.content_path, &pid);
+            ctx.i18n
+                .set_page_language(content_page.metadata.lang.clone());
             let rendered = render_html(
                 &content_page.ops,
                 &content_page.source,
@@ -75,6 +87,18 @@ pub fn render(
                 &ctx.syntax_set,
                 &display_path,
             )
+            .and_then(|mut rendered| {
+                rendered.excerpt_html = render_ranges_html(
+                    &content_page.ops,
+                    &content_page.source,
+                    &content_page.excerpt_ranges,
+                    &engine,
+                    &ctx.theme,
+                    &ctx.syntax_set,
+                    &display_path,
+                )?;
+                Ok(rendered)
+            })
             .map_err(|e| {
                 BarDiagnostic::new(format!("content rendering failed for \"{pid}\"")).with_source(e)
             })?;
@@ -85,6 +109,32 @@ pub fn render(
         }
     }
 
+    // `pid:` links to headings need the table of contents of every page
+    let anchor_ids: HashMap<Arc<str>, Vec<String>> = {
+        let cache = rendered_cache.lock().expect("rendered cache poisoned");
+        let anchor_ids = cache
+            .iter()
+            .map(|(pid, rendered)| (pid.clone(), rendered.anchor_ids()))
+            .collect();
+        for (pid, rendered) in cache.iter().sorted_by_key(|(pid, _)| *pid) {
+            let Some(content_page) = pages.get(pid) else {
+                continue;
+            };
+            let display_path =
+                yamd_display_path(&ctx.config.path, &ctx.config.config.content_path, pid);
+            check_heading_links(
+                &rendered.heading_links,
+                &content_page.source,
+                &display_path,
+                &anchor_ids,
+            )
+            .map_err(|e| {
+                BarDiagnostic::new(format!("content rendering failed for \"{pid}\"")).with_source(e)
+            })?;
+        }
+        anchor_ids
+    };
+
     while let Some(page) = site.next_unrendered_dynamic_page() {
         debug!("Rendering page: {}", page.path);
         let mut context = Context::new();
@@ -93,18 +143,57 @@ pub fn render(
         context.insert("description", &page.description);
         context.insert("path", &page.path);
         context.insert("page_num", &page.page_num);
-        let pid = page.path.trim_end_matches(".html");
-        if let Some(rendered) = rendered_cache
-            .lock()
-            .expect("rendered cache poisoned")
-            .get(pid)
-        {
-            context.insert("fragment_styles", &rendered.css);
-            context.insert("rendered_body", &rendered.html);
-        } else {
-            context.insert("fragment_styles", "");
-            context.insert("rendered_body", "");
+        if let Some(pagination) = &page.pagination {
+            let empty = BTreeSet::new();
+            let view = pages
+                .in_language(pagination.lang.as_deref())
+                .unwrap_or(pages);
+            let articles = view
+                .get_articles(pagination.tag.as_deref())
+                .unwrap_or(&empty);
+            let slice = PagesSlice::new(
+                articles,
+                pagination.per_page,
+                page.page_num * pagination.per_page,
+            );
+            context.insert("slice", &slice);
+            context.insert(
+                "tag",
+                &pagination.tag.as_deref().and_then(|tag| view.get_tag(tag)),
+            );
+            context.insert("prev_url", &pagination.prev);
+            context.insert("next_url", &pagination.next);
         }
+        if let Some(section) = pages.get_section_by_path(&page.path) {
+            context.insert("section", &section);
+        }
+        let content_page = pages.get_by_path(&page.path);
+        ctx.i18n.set_page_language(
+            content_page
+                .and_then(|content_page| content_page.metadata.lang.clone())
+                .or_else(|| page.pagination.as_ref().and_then(|p| p.lang.clone())),
+        );
+        context.insert("page", &content_page);
+        context.insert(
+            "alternates",
+            &content_page.map_or_else(Vec::new, |content_page| {
+                pages.get_alternates(&content_page.pid, config.domain.as_ref())
+            }),
+        );
+        let mut rendered = content_page.and_then(|content_page| {
+            rendered_cache
+                .lock()
+                .expect("rendered cache poisoned")
+                .get(&content_page.pid)
+                .cloned()
+        });
+        if let Some(record) = &page.record {
+            context.insert("record", record.value.as_ref());
+            let body =
+                render_record_body(record, &engine, &ctx.theme, &ctx.syntax_set, &anchor_ids)?;
+            rendered = body.or(rendered);
+        }
+        insert_rendered(&mut context, rendered.as_ref());
         let result = tera.render(&page.template, &context).map_err(|e| {
             let names = tera_error_names(&e);
             let inner: BarDiagnostic = e.into();
@@ -126,11 +215,30 @@ pub fn render(
 
             diag.with_source(inner)
         })?;
+        // the template paginated the page it was rendering, render it again with pagination
+        if site.get_page(&page.path).is_some_and(|current| {
+            matches!(current.as_ref(), Page::Dynamic(current) if current.pagination != page.pagination)
+        }) {
+            continue;
+        }
         site.set_page_content(&page.path, result.into());
-        if let Some(page) = pages.get(page.path.trim_end_matches(".html")) {
-            feed_items.push(FeedItem::new(page, config.domain.as_ref()));
+        if let Some(page) = content_page
+            && page.metadata.is_article()
+        {
+            // an explicit preview is the whole feed content, the excerpt is only a fallback
+            let excerpt_html = rendered_cache
+                .lock()
+                .expect("rendered cache poisoned")
+                .get(&page.pid)
+                .filter(|_| page.metadata.preview.is_none())
+                .map(|rendered| rendered.excerpt_html.clone());
+            feed_items.push(
+                FeedItem::new(page, config.domain.as_ref(), &config.authors, pages)
+                    .with_content_html(excerpt_html),
+            );
         }
     }
+    ctx.i18n.set_page_language(None);
 
     feed_items.sort_by(|b, a| a.date_published.cmp(&b.date_published));
 
@@ -147,6 +255,13 @@ pub fn render(
             None
         };
 
+        let language = page.lang.clone().unwrap_or_else(|| config.language.clone());
+        let items: Vec<FeedItem> = feed_items
+            .iter()
+            .filter(|item| page.lang.is_none() || item.language == page.lang)
+            .cloned()
+            .collect();
+
         match page.typ {
             FeedType::Json => {
                 let feed_url = config.domain.join(&page.path)?;
@@ -156,11 +271,11 @@ pub fn render(
                     feed_url,
                     icon,
                     favicon,
-                    language: config.language.clone(),
+                    language,
                 }
                 .build();
 
-                feed.add_items(feed_items.clone());
+                feed.add_items(items);
                 site.set_page_content(&page.path, feed.to_string().into());
             }
             FeedType::Atom => {
@@ -168,7 +283,7 @@ pub fn render(
                     .title(config.title.as_ref().to_string())
                     .link(config.domain.to_string())
                     .description(config.description.as_ref().to_string())
-                    .language(Some(config.language.as_ref().into()))
+                    .language(Some(language.as_ref().into()))
                     .image(icon.map(|url| rss::Image {
                         url: url.to_string(),
                         title: config.title.as_ref().to_string(),
@@ -178,7 +293,7 @@ pub fn render(
                         description: None,
                     }))
                     .items(
-                        feed_items
+                        items
                             .iter()
                             .map(super::json_feed::FeedItem::to_rss_item)
                             .collect::<Vec<Item>>(),
@@ -193,6 +308,57 @@ pub fn render(
     Ok(())
 }
 
+/// Insert the rendered body of a page with its styles and table of contents, empty ones if
+/// the page has no body.
+fn insert_rendered(context: &mut Context, rendered: Option<&RenderedContent>) {
+    if let Some(rendered) = rendered {
+        context.insert("fragment_styles", &rendered.css);
+        context.insert("rendered_body", &rendered.html);
+        context.insert("toc", &rendered.toc);
+    } else {
+        context.insert("fragment_styles", "");
+        context.insert("rendered_body", "");
+        context.insert("toc", &Vec::<TocEntry>::new());
+    }
+}
+
+/// Render the yamd body field of a data record, `None` if the record has no body field or
+/// leaves it empty.
+fn render_record_body(
+    record: &DataRecord,
+    engine: &FragmentEngine,
+    theme: &Theme,
+    syntax_set: &SyntaxSet,
+    anchor_ids: &HashMap<Arc<str>, Vec<String>>,
+) -> Result<Option<RenderedContent>, BarDiagnostic> {
+    let Some(field) = &record.body else {
+        return Ok(None);
+    };
+    let body = match record.value.get(field.as_ref()) {
+        None | Some(serde_json::Value::Null) => return Ok(None),
+        Some(serde_json::Value::String(body)) => body,
+        Some(_) => {
+            return Err(BarDiagnostic::new(format!(
+                "field '{field}' of {} must be yamd text",
+                record.source
+            )));
+        }
+    };
+    let ops = yamd::op::parse(body);
+    render_html(&ops, body, engine, theme, syntax_set, &record.source)
+        .and_then(|rendered| {
+            check_heading_links(&rendered.heading_links, body, &record.source, anchor_ids)?;
+            Ok(Some(rendered))
+        })
+        .map_err(|e| {
+            BarDiagnostic::new(format!(
+                "content rendering failed for \"{}\"",
+                record.source
+            ))
+            .with_source(e)
+        })
+}
+
 /// Walk a `tera::Error` chain and collect the structured names tera attaches
 /// to each link — function, filter, test, template, and inheritance names.
 /// `Msg`, `Json`, `Io`, and `Utf8Conversion` carry no structured name and are
@@ -298,3 +464,83 @@ mod path_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod record_tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use tera::Context;
+
+    use crate::{render::FragmentEngine, site::DataRecord, syntax_highlight::init, theme::Theme};
+
+    use super::{insert_rendered, render_record_body};
+
+    const THEME_TOML: &str = r#"
+[theme]
+name = "test"
+version = "1.0.0"
+description = "Test theme"
+compatible_bar_versions = ">=0.1.0"
+tags = []
+
+[render]
+lazy_images = true
+heading_anchors = true
+"#;
+
+    fn record(value: serde_json::Value, body: Option<&str>) -> DataRecord {
+        DataRecord {
+            source: "data/hikes.csv#1".into(),
+            value: Arc::new(value),
+            body: body.map(Into::into),
+        }
+    }
+
+    fn render_body(record: &DataRecord) -> Result<Context, crate::diagnostic::BarDiagnostic> {
+        let theme = Theme::parse(THEME_TOML).expect("theme");
+        let syntax_set = init().expect("syntax set");
+        let engine = FragmentEngine::build(Path::new("/tmp"), &theme, None).expect("engine");
+        let rendered = render_record_body(record, &engine, &theme, &syntax_set, &HashMap::new())?;
+        let mut context = Context::new();
+        insert_rendered(&mut context, rendered.as_ref());
+        Ok(context)
+    }
+
+    #[test]
+    fn record_body_becomes_rendered_body() {
+        let hike = record(
+            serde_json::json!({"title": "Eiger", "body": "# Day 1\n\nUp, then down"}),
+            Some("body"),
+        );
+        let context = render_body(&hike).unwrap();
+        let body = context
+            .get("rendered_body")
+            .and_then(|v| v.as_str())
+            .unwrap();
+        assert!(body.contains("Day 1"), "got: {body}");
+        assert!(body.contains("<p>Up, then down</p>"), "got: {body}");
+        assert_eq!(
+            context.get("toc").and_then(|v| v.as_array()).map(Vec::len),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn record_without_body_text_renders_empty_or_fails() {
+        let empty = record(serde_json::json!({"title": "Eiger"}), Some("body"));
+        let context = render_body(&empty).unwrap();
+        assert_eq!(
+            context.get("rendered_body").and_then(|v| v.as_str()),
+            Some("")
+        );
+
+        let number = record(serde_json::json!({"body": 5}), Some("body"));
+        let err = render_body(&number).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "field 'body' of data/hikes.csv#1 must be yamd text"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use crate::{
+    config::SimilarityConfig,
+    pages::{Page, plain_text},
+};
+
+/// Words shorter than this are left out of content vectors.
+const MIN_TERM_CHARS: usize = 3;
+
+/// Page similar to another one, with a score between 0 and 1.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Similar {
+    pub pid: Arc<str>,
+    pub score: f64,
+}
+
+/// Sparse term weights scaled to unit length, so the dot product of two vectors is their
+/// cosine similarity. Terms are sorted, so sums always add up in the same order and equal
+/// vectors get exactly equal scores.
+#[derive(Debug, Clone, Default)]
+struct TermVector(BTreeMap<Arc<str>, f64>);
+
+impl TermVector {
+    fn new(mut weights: BTreeMap<Arc<str>, f64>) -> Self {
+        let norm = weights
+            .values()
+            .map(|weight| weight * weight)
+            .sum::<f64>()
+            .sqrt();
+        if norm > 0.0 {
+            for weight in weights.values_mut() {
+                *weight /= norm;
+            }
+        } else {
+            weights.clear();
+        }
+        Self(weights)
+    }
+
+    fn cosine(&self, other: &Self) -> f64 {
+        let (small, large) = if self.0.len() <= other.0.len() {
+            (&self.0, &other.0)
+        } else {
+            (&other.0, &self.0)
+        };
+        small
+            .iter()
+            .filter_map(|(term, weight)| large.get(term).map(|other| weight * other))
+            .sum()
+    }
+}
+
+/// Tag and content vectors of every page, weighted by inverse document frequency over
+/// `candidates`.
+#[derive(Debug, Default)]
+pub struct SimilarityIndex {
+    weights: SimilarityConfig,
+    tags: HashMap<Arc<str>, TermVector>,
+    content: HashMap<Arc<str>, TermVector>,
+    /// Pages that can be suggested, in `Page::cmp` order.
+    candidates: Vec<Arc<Page>>,
+}
+
+impl SimilarityIndex {
+    /// Indexes `pages` with their normalized tags. Only `candidates` count for document
+    /// frequencies and can be suggested. Content vectors are built only with a content weight.
+    #[must_use]
+    pub fn new<'a>(
+        weights: SimilarityConfig,
+        pages: impl Iterator<Item = (&'a Arc<Page>, Vec<Arc<str>>)>,
+        candidates: Vec<Arc<Page>>,
+    ) -> Self {
+        let candidate_pids = candidates
+            .iter()
+            .map(|page| page.pid.clone())
+            .collect::<HashSet<_>>();
+        let mut tag_terms = HashMap::new();
+        let mut content_terms = HashMap::new();
+        for (page, tags) in pages {
+            let tags = tags
+                .into_iter()
+                .map(|tag| (tag, 1))
+                .collect::<HashMap<_, _>>();
+            tag_terms.insert(page.pid.clone(), tags);
+            if weights.content > 0.0 {
+                content_terms.insert(page.pid.clone(), terms(page));
+            }
+        }
+
+        let documents = candidates.len();
+        let vectors = |terms: HashMap<Arc<str>, HashMap<Arc<str>, usize>>| {
+            let mut frequencies: HashMap<Arc<str>, usize> = HashMap::new();
+            for (pid, terms) in &terms {
+                if candidate_pids.contains(pid) {
+                    for term in terms.keys() {
+                        *frequencies.entry(term.clone()).or_default() += 1;
+                    }
+                }
+            }
+            terms
+                .into_iter()
+                .map(|(pid, terms)| {
+                    let weights = terms
+                        .into_iter()
+                        .map(|(term, count)| {
+                            let idf = idf(documents, frequencies.get(&term).copied().unwrap_or(0));
+                            (term, tf(count) * idf)
+                        })
+                        .collect();
+                    (pid, TermVector::new(weights))
+                })
+                .collect::<HashMap<_, _>>()
+        };
+
+        Self {
+            weights,
+            tags: vectors(tag_terms),
+            content: vectors(content_terms),
+            candidates,
+        }
+    }
+
+    /// Up to `max` candidates most similar to `pid`, best first. Equal scores keep the
+    /// `Page::cmp` order, so newer pages come first. Pages with a zero score are left out.
+    #[must_use]
+    pub fn similar(&self, pid: &str, max: usize) -> Vec<Similar> {
+        let total = self.weights.tags + self.weights.content;
+        if total <= 0.0 {
+            return vec![];
+        }
+        let empty = TermVector::default();
+        let tags = self.tags.get(pid).unwrap_or(&empty);
+        let content = self.content.get(pid).unwrap_or(&empty);
+
+        let mut similar = self
+            .candidates
+            .iter()
+            .filter(|page| &*page.pid != pid)
+            .filter_map(|page| {
+                let tag_score = self
+                    .tags
+                    .get(&page.pid)
+                    .map_or(0.0, |other| tags.cosine(other));
+                let content_score = self
+                    .content
+                    .get(&page.pid)
+                    .map_or(0.0, |other| content.cosine(other));
+                let score =
+                    (self.weights.tags * tag_score + self.weights.content * content_score) / total;
+                (score > 0.0).then(|| Similar {
+                    pid: page.pid.clone(),
+                    score,
+                })
+            })
+            .collect::<Vec<_>>();
+        // stable sort keeps candidates order for equal scores
+        similar.sort_by(|left, right| {
+            right
+                .score
+                .partial_cmp(&left.score)
+                .unwrap_or(Ordering::Equal)
+        });
+        similar.truncate(max);
+        similar
+    }
+}
+
+/// Smoothed inverse document frequency, always positive so common terms still count a little.
+#[allow(clippy::cast_precision_loss)]
+fn idf(documents: usize, frequency: usize) -> f64 {
+    ((1 + documents) as f64 / (1 + frequency) as f64).ln() + 1.0
+}
+
+/// Sublinear term frequency, so a word repeated many times does not dominate.
+#[allow(clippy::cast_precision_loss)]
+fn tf(count: usize) -> f64 {
+    1.0 + (count as f64).ln()
+}
+
+/// Lowercase words of the page prose with their counts.
+fn terms(page: &Page) -> HashMap<Arc<str>, usize> {
+    let (prose, _) = plain_text(&page.ops, &page.source);
+    let mut terms: HashMap<Arc<str>, usize> = HashMap::new();
+    for word in prose
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_TERM_CHARS)
+    {
+        *terms.entry(Arc::from(word.to_lowercase())).or_default() += 1;
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(terms: &[(&str, f64)]) -> TermVector {
+        TermVector::new(
+            terms
+                .iter()
+                .map(|(term, weight)| (Arc::from(*term), *weight))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn cosine_of_unit_vectors() {
+        let left = vector(&[("a", 1.0), ("b", 1.0)]);
+        let right = vector(&[("a", 2.0), ("b", 2.0)]);
+        assert!((left.cosine(&right) - 1.0).abs() < 1e-9);
+        assert!(left.cosine(&vector(&[("c", 1.0)])).abs() < 1e-9);
+        assert!(left.cosine(&TermVector::default()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn equal_scores_keep_page_order() {
+        let tags = [
+            "rust", "web", "cli", "async", "tokio", "serde", "wasm", "http",
+        ];
+        let page = |pid: &str, days_ago: i64| {
+            let source = "Building command line tools with async runtimes and servers";
+            Arc::new(Page::new(
+                pid.into(),
+                yamd::op::parse(source),
+                source.to_string(),
+                crate::metadata::Metadata {
+                    date: (chrono::Utc::now() - chrono::Duration::days(days_ago)).into(),
+                    ..crate::metadata::Metadata::default()
+                },
+            ))
+        };
+        let mut candidates = [("/e", 5), ("/b", 2), ("/d", 4), ("/a", 1), ("/c", 3)]
+            .map(|(pid, days_ago)| page(pid, days_ago))
+            .to_vec();
+        candidates.sort();
+        let index = SimilarityIndex::new(
+            SimilarityConfig {
+                tags: 1.0,
+                content: 1.0,
+            },
+            candidates
+                .iter()
+                .map(|page| (page, tags.iter().map(|tag| Arc::from(*tag)).collect())),
+            candidates.clone(),
+        );
+
+        let similar = index.similar("/c", 10);
+        assert_eq!(
+            similar.iter().map(|s| s.pid.as_ref()).collect::<Vec<_>>(),
+            ["/a", "/b", "/d", "/e"]
+        );
+        assert!(
+            similar
+                .iter()
+                .all(|s| s.score.to_bits() == similar[0].score.to_bits())
+        );
+    }
+
+    #[test]
+    fn rare_terms_weigh_more() {
+        assert!(idf(10, 1) > idf(10, 5));
+        assert!(idf(10, 10) > 0.0);
+    }
+}
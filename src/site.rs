@@ -6,12 +6,15 @@ use std::{
 
 use tokio::fs::{copy, create_dir_all, remove_dir_all};
 use tracing::{debug, info};
+use url::Url;
 
 use crate::{
     r#async::try_for_each,
+    config::Config,
     context::BuildConfig,
     diagnostic::{BarDiagnostic, ContextExt},
     fs::{canonicalize_with_context, get_files_by_ext_deep, write_file},
+    pages::Pages,
 };
 
 use tracing::warn;
@@ -24,6 +27,29 @@ pub struct DynamicPage {
     pub description: Arc<str>,
     pub content: Option<Arc<str>>,
     pub page_num: usize,
+    pub pagination: Option<Pagination>,
+    /// Data record the page is generated from.
+    pub record: Option<DataRecord>,
+}
+
+/// Slice of articles listed by a paginated dynamic page, `page_num` is the slice index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pagination {
+    pub tag: Option<Arc<str>>,
+    pub lang: Option<Arc<str>>,
+    pub per_page: usize,
+    pub prev: Option<Arc<str>>,
+    pub next: Option<Arc<str>>,
+}
+
+/// Record of a data file passed to the template of a generated page as `record`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRecord {
+    /// Data file and record number, e.g. `data/talks.yaml#2`.
+    pub source: Arc<str>,
+    pub value: Arc<serde_json::Value>,
+    /// Field of the record with yamd content rendered as the page body.
+    pub body: Option<Arc<str>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -38,6 +64,8 @@ pub struct Feed {
     pub path: Arc<str>,
     pub content: Option<Arc<str>>,
     pub typ: FeedType,
+    /// Only articles in this language, all articles if `None`.
+    pub lang: Option<Arc<str>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -98,6 +126,16 @@ impl Page {
             Self::Feed(page) => page.path.clone(),
         }
     }
+
+    /// File the page is written to, relative to the dist folder.
+    #[must_use]
+    pub fn file(&self) -> String {
+        match self {
+            Self::Static(page) => page.destination.trim_start_matches('/').to_string(),
+            Self::Dynamic(page) => dynamic_page_file(&page.path),
+            Self::Feed(page) => page.path.trim_start_matches('/').to_string(),
+        }
+    }
 }
 
 pub struct Site {
@@ -124,6 +162,27 @@ impl Site {
         }
     }
 
+    /// Register `page`, replacing the page registered at the same path.
+    ///
+    /// # Panics
+    /// Panics if the pages mutex is poisoned.
+    pub fn replace_page(&self, page: Page) {
+        let mut pages = self.pages.lock().expect("Site pages mutex poisoned");
+        pages.insert(page.get_path(), Arc::new(page));
+    }
+
+    /// Register a meta-refresh page at `from` that points to `to`.
+    pub fn add_redirect(&self, from: &str, to: &Url) {
+        self.add_page(
+            StaticPage {
+                destination: dynamic_page_file(from).into(),
+                source: None,
+                fallback: Some(redirect_html(to).into()),
+            }
+            .into(),
+        );
+    }
+
     /// # Panics
     /// Panics if the pages mutex is poisoned.
     pub fn get_page(&self, path: &str) -> Option<Arc<Page>> {
@@ -131,6 +190,16 @@ impl Site {
         pages.get(path).cloned()
     }
 
+    /// Page written to `file`, relative to the dist folder, whatever path it was added with.
+    ///
+    /// # Panics
+    /// Panics if the pages mutex is poisoned.
+    pub fn get_page_by_file(&self, file: &str) -> Option<Arc<Page>> {
+        let file = file.trim_start_matches('/');
+        let pages = self.pages.lock().expect("Site pages mutex poisoned");
+        pages.values().find(|page| page.file() == file).cloned()
+    }
+
     /// # Panics
     /// Panics if the pages mutex is poisoned.
     pub fn next_unrendered_dynamic_page(&self) -> Option<DynamicPage> {
@@ -195,19 +264,14 @@ impl Site {
                 }
                 Page::Dynamic(dynamic) => {
                     *page = Arc::new(Page::Dynamic(DynamicPage {
-                        path: dynamic.path.clone(),
                         content: Some(content),
-                        template: dynamic.template.clone(),
-                        title: dynamic.title.clone(),
-                        description: dynamic.description.clone(),
-                        page_num: dynamic.page_num,
+                        ..dynamic.clone()
                     }));
                 }
                 Page::Feed(feed) => {
                     *page = Arc::new(Page::Feed(Feed {
-                        path: feed.path.clone(),
                         content: Some(content),
-                        typ: feed.typ.clone(),
+                        ..feed.clone()
                     }));
                 }
             });
@@ -275,13 +339,7 @@ async fn save_page((dist_folder, page): (Arc<PathBuf>, Arc<Page>)) -> Result<(),
         }
         Page::Dynamic(page) => {
             if let Some(content) = &page.content {
-                let page_path = if page.path == "/".into() {
-                    "index.html"
-                } else {
-                    page.path.trim_start_matches('/')
-                };
-
-                let path = dist_folder.join(page_path);
+                let path = dist_folder.join(dynamic_page_file(&page.path));
                 debug!("write to file: {}", path.clone().display());
                 write_file(&path, content.as_bytes()).await?;
             }
@@ -297,6 +355,23 @@ async fn save_page((dist_folder, page): (Arc<PathBuf>, Arc<Page>)) -> Result<(),
     Ok(())
 }
 
+/// File a page path is written to, relative to the dist folder. Paths ending with `/` are
+/// directory indexes.
+pub(crate) fn dynamic_page_file(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    if path.is_empty() || path.ends_with('/') {
+        format!("{path}index.html")
+    } else {
+        path.to_string()
+    }
+}
+
+fn redirect_html(to: &Url) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Redirecting to {to}</title>\n<link rel=\"canonical\" href=\"{to}\">\n<meta http-equiv=\"refresh\" content=\"0; url={to}\">\n</head>\n<body>\n<a href=\"{to}\">{to}</a>\n</body>\n</html>\n"
+    )
+}
+
 fn create_destination_path(source: &Path, prefix: &PathBuf) -> Result<String, BarDiagnostic> {
     let stripped = source.strip_prefix(prefix).with_context(|| {
         format!(
@@ -380,11 +455,13 @@ pub async fn init_site(build_config: &BuildConfig) -> Result<Arc<Site>, BarDiagn
             description: config.description.clone(),
             content: None,
             page_num: 0,
+            pagination: None,
+            record: None,
         }
         .into(),
     );
 
-    if config.template.join("404.html").exists() {
+    if base_path.join(&config.template).join("404.html").exists() {
         site.add_page(
             DynamicPage {
                 path: "/404.html".into(),
@@ -393,6 +470,8 @@ pub async fn init_site(build_config: &BuildConfig) -> Result<Arc<Site>, BarDiagn
                 description: config.description.clone(),
                 content: None,
                 page_num: 0,
+                pagination: None,
+                record: None,
             }
             .into(),
         );
@@ -402,6 +481,242 @@ pub async fn init_site(build_config: &BuildConfig) -> Result<Arc<Site>, BarDiagn
     Ok(site)
 }
 
+/// Register an index page for every content subdirectory. Template comes from the section's
+/// `_index.yamd` (already registered by `add_content_pages`), else `config.section_template`
+/// if the theme in `template_dir` has it.
+///
+/// # Errors
+/// Returns error if a section index would be published at the path of a content page.
+pub fn add_section_pages(
+    site: &Site,
+    pages: &Pages,
+    config: &Config,
+    template_dir: &Path,
+) -> Result<(), BarDiagnostic> {
+    let has_default_template = template_dir.join(config.section_template.as_ref()).exists();
+    let languages = pages.languages();
+    let views: Vec<&Pages> = if languages.is_empty() {
+        vec![pages]
+    } else {
+        languages
+            .iter()
+            .filter_map(|lang| pages.in_language(Some(lang)))
+            .collect()
+    };
+    for (view, key) in views
+        .into_iter()
+        .flat_map(|view| view.section_keys().into_iter().map(move |key| (view, key)))
+    {
+        let Some(section) = view.get_section(&key) else {
+            continue;
+        };
+        if section.path.as_ref() == "/" || !has_default_template {
+            continue;
+        }
+        let index = section.index.as_ref();
+        if index.is_some_and(|index| index.metadata.template.is_some()) {
+            continue;
+        }
+        if let Some(other) = view.get_by_path(&section.path).filter(|other| {
+            other.path == section.path && index.is_none_or(|index| index.pid != other.pid)
+        }) {
+            return Err(BarDiagnostic::new(format!(
+                "index of section {key} collides with page {} at {}",
+                other.pid, section.path
+            ))
+            .with_help(format!(
+                "make the page the section index {key}/_index.yamd, or set a different `slug` in its metadata"
+            )));
+        }
+        site.add_page(
+            DynamicPage {
+                path: section.path.clone(),
+                template: config.section_template.clone(),
+                title: section.title.clone(),
+                description: index
+                    .and_then(|index| index.metadata.preview.as_deref())
+                    .unwrap_or_default()
+                    .into(),
+                content: None,
+                page_num: 0,
+                pagination: None,
+                record: None,
+            }
+            .into(),
+        );
+    }
+    Ok(())
+}
+
+/// Register a dynamic page for every content page that names its template in metadata.
+pub fn add_content_pages(site: &Site, pages: &Pages) {
+    for pid in pages.keys() {
+        let Some(page) = pages.get(&pid) else {
+            continue;
+        };
+        let Some(template) = &page.metadata.template else {
+            continue;
+        };
+        site.add_page(
+            DynamicPage {
+                path: page.path.clone(),
+                template: template.clone(),
+                title: page.metadata.title.as_str().into(),
+                description: page.metadata.preview.as_deref().unwrap_or_default().into(),
+                content: None,
+                page_num: 0,
+                pagination: None,
+                record: None,
+            }
+            .into(),
+        );
+    }
+}
+
+/// Write the graph of links between content pages to `config.link_graph`, if set.
+///
+/// # Errors
+/// Returns error if the graph cannot be serialized.
+pub fn add_link_graph(site: &Site, pages: &Pages, config: &Config) -> Result<(), BarDiagnostic> {
+    let Some(destination) = &config.link_graph else {
+        return Ok(());
+    };
+    let graph = serde_json::to_string(&pages.get_link_graph())?;
+    site.add_page(
+        StaticPage {
+            destination: destination.trim_start_matches('/').into(),
+            source: None,
+            fallback: Some(graph.into()),
+        }
+        .into(),
+    );
+    Ok(())
+}
+
+/// URL path an alias redirects from. Aliases ending with `.html` or `/` are used as they are,
+/// anything else is treated as an old pid.
+fn alias_path(alias: &str, config: &Config) -> String {
+    let alias = format!("/{}", alias.trim().trim_start_matches('/'));
+    let is_html = Path::new(&alias)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html"));
+    if alias.ends_with('/') || is_html {
+        alias
+    } else {
+        config.page_path(&alias)
+    }
+}
+
+/// Register a redirect for every alias in content page metadata, plus the redirect manifests
+/// enabled in `config.redirect_manifests`. Runs after rendering, so theme pages are known.
+///
+/// # Errors
+/// Returns error if an alias collides with a content page, any other site page or another
+/// alias.
+pub fn add_alias_redirects(
+    site: &Site,
+    pages: &Pages,
+    config: &Config,
+) -> Result<(), BarDiagnostic> {
+    let mut redirects: Vec<(String, Arc<str>, Arc<str>)> = vec![];
+    let mut pids = pages.keys();
+    pids.sort();
+    for pid in pids {
+        let Some(page) = pages.get(&pid) else {
+            continue;
+        };
+        for alias in page.metadata.aliases.iter().flatten() {
+            let from = alias_path(alias, config);
+            if let Some(other) = pages
+                .get_by_path(&from)
+                .filter(|other| *other.path == *from)
+            {
+                return Err(BarDiagnostic::new(format!(
+                    "alias '{alias}' of {pid} collides with page {}",
+                    other.pid
+                )));
+            }
+            if let Some(other) = site.get_page_by_file(&dynamic_page_file(&from)) {
+                return Err(BarDiagnostic::new(format!(
+                    "alias '{alias}' of {pid} collides with {} {}",
+                    match other.as_ref() {
+                        Page::Static(_) => "static file",
+                        Page::Dynamic(_) => "page",
+                        Page::Feed(_) => "feed",
+                    },
+                    other.get_path()
+                )));
+            }
+            if let Some((_, other, _)) = redirects.iter().find(|(path, _, _)| *path == from) {
+                return Err(BarDiagnostic::new(format!(
+                    "alias '{alias}' of {pid} is already an alias of {other}"
+                )));
+            }
+            redirects.push((from, pid.clone(), page.path.clone()));
+        }
+    }
+
+    for (from, _, to) in &redirects {
+        site.add_redirect(from, &config.domain.join(to)?);
+    }
+
+    if config.redirect_manifests.redirects_file {
+        let manifest = redirects
+            .iter()
+            .map(|(from, _, to)| format!("{from} {to} 301\n"))
+            .collect::<Vec<String>>()
+            .concat();
+        site.add_page(
+            StaticPage {
+                destination: "_redirects".into(),
+                source: None,
+                fallback: Some(manifest.into()),
+            }
+            .into(),
+        );
+    }
+
+    if config.redirect_manifests.nginx_map {
+        let entries = redirects
+            .iter()
+            .map(|(from, _, to)| format!("    {from} {to};\n"))
+            .collect::<Vec<String>>()
+            .concat();
+        let map = format!("map $uri $bar_redirect {{\n{entries}}}\n");
+        site.add_page(
+            StaticPage {
+                destination: "redirects.nginx.conf".into(),
+                source: None,
+                fallback: Some(map.into()),
+            }
+            .into(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Redirect the `<pid>.html` location of every content page to its pretty URL.
+///
+/// # Errors
+/// Returns error if a page path cannot be joined with the site domain.
+pub fn add_pretty_url_redirects(
+    site: &Site,
+    pages: &Pages,
+    config: &Config,
+) -> Result<(), BarDiagnostic> {
+    for pid in pages.keys() {
+        let Some(page) = pages.get(&pid) else {
+            continue;
+        };
+        let stem = page.path.trim_end_matches('/');
+        if !stem.is_empty() {
+            site.add_redirect(&format!("{stem}.html"), &config.domain.join(&page.path)?);
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -446,8 +761,211 @@ mod tests {
             description: "description".into(),
             content: None,
             page_num: 0,
+            pagination: None,
+            record: None,
         };
         assert_eq!(Page::from(page.clone()), Page::Dynamic(page.clone()));
         assert_eq!(Page::from(page.clone()).get_path(), Arc::from("/"));
     }
+
+    #[test]
+    fn dynamic_page_file_resolves_directory_indexes() {
+        assert_eq!(dynamic_page_file("/"), "index.html");
+        assert_eq!(dynamic_page_file("/posts/hello/"), "posts/hello/index.html");
+        assert_eq!(dynamic_page_file("/posts/hello.html"), "posts/hello.html");
+    }
+
+    fn config(extra: &str) -> Config {
+        serde_yaml::from_str(&format!(
+            "dist_path: ./dist
+content_path: ./content
+static_source_path: ./public
+template: ./template
+domain: https://example.com
+title: t
+description: d
+template_config: {{}}
+yamd_processors: {{}}
+{extra}"
+        ))
+        .expect("config")
+    }
+
+    fn page_with_aliases(pid: &str, aliases: &[&str]) -> crate::pages::Page {
+        crate::pages::Page::new(
+            pid.into(),
+            vec![],
+            String::new(),
+            crate::metadata::Metadata {
+                aliases: Some(aliases.iter().map(|a| Arc::from(*a)).collect()),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn fallback(site: &Site, path: &str) -> String {
+        let page = site.get_page(path).expect("page");
+        let Page::Static(page) = page.as_ref() else {
+            panic!("{path} must be a static page");
+        };
+        page.fallback.as_deref().expect("fallback").to_string()
+    }
+
+    #[test]
+    fn alias_redirects_and_manifests() {
+        let config = config("redirect_manifests:\n  redirects_file: true\n  nginx_map: true\n");
+        let site = Site::new(PathBuf::from("/tmp"));
+        let mut pages = Pages::new();
+        pages.push(page_with_aliases("/new", &["old", "/older.html"]));
+
+        add_alias_redirects(&site, &pages, &config).expect("aliases");
+
+        assert!(site.get_page("old.html").is_some());
+        assert!(site.get_page("older.html").is_some());
+        assert_eq!(
+            fallback(&site, "_redirects"),
+            "/old.html /new.html 301\n/older.html /new.html 301\n"
+        );
+        let map = fallback(&site, "redirects.nginx.conf");
+        assert!(map.contains("    /old.html /new.html;\n"), "got: {map}");
+    }
+
+    #[test]
+    fn add_section_pages_uses_section_template() {
+        let template_dir = tempfile::tempdir().unwrap();
+        std::fs::write(template_dir.path().join("section.html"), "").unwrap();
+        let config = config("");
+        let site = Site::new(PathBuf::from("/tmp"));
+        let mut pages = Pages::new();
+        pages.push(page_with_aliases("/travel/alps", &[]));
+        pages.push(page_with_aliases("/notes/_index", &[]));
+        let mut custom = page_with_aliases("/recipes/_index", &[]);
+        custom.metadata.template = Some("recipes.html".into());
+        pages.push(custom);
+        pages.set_section_paths(|section| config.section_path(section));
+
+        add_section_pages(&site, &pages, &config, template_dir.path()).unwrap();
+
+        let travel = site
+            .get_page("/travel.html")
+            .expect("/travel.html must be registered");
+        let Page::Dynamic(travel) = travel.as_ref() else {
+            panic!("/travel.html must be a dynamic page");
+        };
+        assert_eq!(travel.template.as_ref(), "section.html");
+        assert_eq!(travel.title.as_ref(), "travel");
+        assert!(site.get_page("/notes.html").is_some());
+        assert!(site.get_page("/recipes.html").is_none());
+        assert!(site.get_page("/").is_none());
+
+        let site = Site::new(PathBuf::from("/tmp"));
+        add_section_pages(&site, &pages, &config, Path::new("/nonexistent")).unwrap();
+        assert!(site.get_page("/travel.html").is_none());
+    }
+
+    #[test]
+    fn section_index_colliding_with_page_fails() {
+        let template_dir = tempfile::tempdir().unwrap();
+        std::fs::write(template_dir.path().join("section.html"), "").unwrap();
+        let config = config("");
+        let site = Site::new(PathBuf::from("/tmp"));
+        let mut pages = Pages::new();
+        pages.push(page_with_aliases("/travel/alps", &[]));
+        pages.push(page_with_aliases("/travel", &[]));
+        pages.set_section_paths(|section| config.section_path(section));
+
+        let err = add_section_pages(&site, &pages, &config, template_dir.path()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "index of section /travel collides with page /travel at /travel.html"
+        );
+    }
+
+    #[test]
+    fn add_content_pages_uses_metadata_template() {
+        let site = Site::new(PathBuf::from("/tmp"));
+        let mut pages = Pages::new();
+        pages.push(crate::pages::Page::new(
+            "/about".into(),
+            vec![],
+            String::new(),
+            crate::metadata::Metadata {
+                title: "About".into(),
+                template: Some("page.html".into()),
+                ..Default::default()
+            },
+        ));
+        pages.push(page_with_aliases("/post", &[]));
+
+        add_content_pages(&site, &pages);
+
+        let about = site
+            .get_page("/about.html")
+            .expect("/about.html must be registered");
+        let Page::Dynamic(about) = about.as_ref() else {
+            panic!("/about.html must be a dynamic page");
+        };
+        assert_eq!(about.template.as_ref(), "page.html");
+        assert_eq!(about.title.as_ref(), "About");
+        assert!(site.get_page("/post.html").is_none());
+    }
+
+    #[test]
+    fn alias_colliding_with_page_fails() {
+        let config = config("");
+        let site = Site::new(PathBuf::from("/tmp"));
+        let mut pages = Pages::new();
+        pages.push(page_with_aliases("/a", &["/b"]));
+        pages.push(page_with_aliases("/b", &[]));
+
+        let err = add_alias_redirects(&site, &pages, &config).unwrap_err();
+        assert!(
+            err.to_string().contains("collides with page /b"),
+            "got: {err}"
+        );
+    }
+
+    #[test]
+    fn alias_colliding_with_theme_page_fails() {
+        let config = config("");
+        let site = Site::new(PathBuf::from("/tmp"));
+        site.add_page(
+            DynamicPage {
+                path: "/archive/".into(),
+                template: "archive.html".into(),
+                title: "Archive".into(),
+                description: "".into(),
+                content: None,
+                page_num: 0,
+                pagination: None,
+                record: None,
+            }
+            .into(),
+        );
+        let mut pages = Pages::new();
+        pages.push(page_with_aliases("/a", &["archive/index.html"]));
+
+        let err = add_alias_redirects(&site, &pages, &config).unwrap_err();
+        assert!(
+            err.to_string().contains("collides with page /archive/"),
+            "got: {err}"
+        );
+    }
+
+    #[test]
+    fn add_redirect_registers_static_fallback() {
+        let site = Site::new(PathBuf::from("/tmp"));
+        let to = Url::parse("https://example.com/posts/hello/").unwrap();
+        site.add_redirect("/posts/hello.html", &to);
+
+        let html = fallback(&site, "posts/hello.html");
+        assert!(
+            html.contains(r#"<link rel="canonical" href="https://example.com/posts/hello/">"#),
+            "got: {html}"
+        );
+        assert!(
+            html.contains(r#"content="0; url=https://example.com/posts/hello/""#),
+            "got: {html}"
+        );
+    }
 }
@@ -1,11 +1,16 @@
 use crate::{
     cache::{raw_cache_path, shard_prefix},
     context::BuildContext,
+    data::{DATA_DIR, Data, record_path},
     fs::seahash_checksum,
     gpx_embed::gpx,
-    pages::Pages,
+    i18n::I18n,
+    pages::{PageQuery, Pages, PagesSlice, tag_slug},
     render::RenderedContentCache,
-    site::{DynamicPage, Feed, FeedType, Page, Site, StaticPage},
+    site::{
+        DataRecord, DynamicPage, Feed, FeedType, Page, Pagination, Site, StaticPage,
+        dynamic_page_file,
+    },
 };
 use cloudinary::transformation::{
     Image, Transformations,
@@ -17,8 +22,9 @@ use cloudinary::transformation::{
 };
 use data_encoding::BASE64URL_NOPAD;
 use gpxtools::{StatsArgs, calculate_stats};
+use itertools::Itertools;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     hash::BuildHasher,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
@@ -142,6 +148,62 @@ fn get_usize_arg(args: &HashMap<String, Value>, key: &str) -> Option<usize> {
         .map(|n| n as usize)
 }
 
+/// Pages in the language of the page with `pid`, so neighbours and suggestions are never
+/// translations. All pages if the page is not found.
+fn page_language<'a>(pages: &'a Pages, pid: &str) -> &'a Pages {
+    let lang = pages.get(pid).and_then(|page| page.metadata.lang.clone());
+    pages.in_language(lang.as_deref()).unwrap_or(pages)
+}
+
+/// Page count of the `limit` arg of functions returning a `PagesSlice`, 3 without it.
+fn get_limit_arg(args: &HashMap<String, Value>) -> Result<usize> {
+    match get_usize_arg(args, "limit") {
+        Some(0) => Err(tera::Error::msg("limit must be greater than 0")),
+        limit => Ok(limit.unwrap_or(3)),
+    }
+}
+
+/// Pages in the language of the `lang` arg, all pages without it.
+fn pages_in<'a>(pages: &'a Pages, args: &HashMap<String, Value>) -> Result<&'a Pages> {
+    let lang = get_string_arg(args, "lang");
+    pages.in_language(lang.as_deref()).ok_or_else(|| {
+        tera::Error::msg(format!("language '{}' not found", lang.unwrap_or_default()))
+    })
+}
+
+/// String or array of strings.
+fn get_str_list_arg(args: &HashMap<String, Value>, key: &str) -> Vec<Arc<str>> {
+    match args.get(key) {
+        Some(Value::String(value)) => vec![value.as_str().into()],
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(Arc::from)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// RFC 3339 date time or `YYYY-MM-DD` date at midnight UTC.
+fn get_date_arg(
+    args: &HashMap<String, Value>,
+    key: &str,
+) -> Result<Option<chrono::DateTime<chrono::FixedOffset>>> {
+    let Some(raw) = get_string_arg(args, key) else {
+        return Ok(None);
+    };
+    chrono::DateTime::parse_from_rfc3339(&raw)
+        .or_else(|_| {
+            chrono::NaiveDate::parse_from_str(&raw, "%Y-%m-%d").map(|date| {
+                date.and_time(chrono::NaiveTime::MIN)
+                    .and_utc()
+                    .fixed_offset()
+            })
+        })
+        .map(Some)
+        .map_err(|e| tera::Error::msg(format!("could not parse {key} '{raw}' as date: {e}")))
+}
+
 fn resolve_in_project(project_path: &Path, raw: &str) -> Result<PathBuf> {
     let rel = crate::fs::normalize_project_rel(raw).map_err(tera::Error::msg)?;
     Ok(project_path.join(rel))
@@ -178,6 +240,8 @@ fn add_page(site: Arc<Site>) -> impl Function + 'static {
                 description: description.into(),
                 page_num,
                 content: None,
+                pagination: None,
+                record: None,
             }
             .into(),
         );
@@ -185,6 +249,225 @@ fn add_page(site: Arc<Site>) -> impl Function + 'static {
     }
 }
 
+fn add_data_pages(site: Arc<Site>, data: Arc<Data>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let file = get_string_arg(args, "path")
+            .ok_or_else(|| tera::Error::msg("path is required for add_data_pages"))?;
+        let template = get_arc_str_arg(args, "template")
+            .ok_or_else(|| tera::Error::msg("template is required for add_data_pages"))?;
+        let pattern = get_string_arg(args, "path_pattern")
+            .ok_or_else(|| tera::Error::msg("path_pattern is required for add_data_pages"))?;
+        let body = get_arc_str_arg(args, "body");
+        let (name, records) = data.resolve(&file).ok_or_else(|| {
+            tera::Error::msg(format!("data file '{file}' not found in {DATA_DIR}/"))
+        })?;
+        let records = records
+            .as_array()
+            .ok_or_else(|| tera::Error::msg(format!("data file '{file}' is not a list")))?;
+
+        let mut paths: Vec<Arc<str>> = vec![];
+        for (n, record) in (1..).zip(records) {
+            let path: Arc<str> = record_path(&pattern, record, n)
+                .map_err(|e| tera::Error::msg(format!("{file}: {e}")))?
+                .into();
+            if let Some(other) = paths.iter().position(|other| *other == path) {
+                return Err(tera::Error::msg(format!(
+                    "{file}: records {} and {n} are both published at {path}",
+                    other + 1
+                )));
+            }
+            let field = |name: &str| -> Arc<str> {
+                record
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .into()
+            };
+            let source: Arc<str> = format!("{DATA_DIR}/{name}#{n}").into();
+            match site.get_page_by_file(&dynamic_page_file(&path)).as_deref() {
+                None => {}
+                // the calling template is rendered again, e.g. once per paginated page
+                Some(Page::Dynamic(other))
+                    if other.template == template
+                        && other.record.as_ref().is_some_and(|r| r.source == source) => {}
+                Some(other) => {
+                    let other = match other {
+                        Page::Dynamic(DynamicPage {
+                            record: Some(record),
+                            ..
+                        }) => format!("record {}", record.source),
+                        other => format!("page {}", other.get_path()),
+                    };
+                    return Err(tera::Error::msg(format!(
+                        "{file}: record {n} at {path} collides with {other}"
+                    )));
+                }
+            }
+            site.add_page(
+                DynamicPage {
+                    path: path.clone(),
+                    template: template.clone(),
+                    title: field("title"),
+                    description: field("description"),
+                    content: None,
+                    page_num: 0,
+                    pagination: None,
+                    record: Some(DataRecord {
+                        source,
+                        value: Arc::new(record.clone()),
+                        body: body.clone(),
+                    }),
+                }
+                .into(),
+            );
+            paths.push(path);
+        }
+        Ok(tera::to_value(paths)?)
+    }
+}
+
+fn paginate(
+    site: Arc<Site>,
+    pages: Arc<Pages>,
+    config: Arc<crate::config::Config>,
+) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let template = get_arc_str_arg(args, "template")
+            .ok_or_else(|| tera::Error::msg("template is required for paginate"))?;
+        let pattern = get_string_arg(args, "path_pattern")
+            .ok_or_else(|| tera::Error::msg("path_pattern is required for paginate"))?;
+        if !pattern.contains("{n}") {
+            return Err(tera::Error::msg(format!(
+                "path_pattern '{pattern}' must contain {{n}}"
+            )));
+        }
+        let first_path = get_string_arg(args, "first_path");
+        let per_page = match get_usize_arg(args, "per_page") {
+            Some(0) => return Err(tera::Error::msg("per_page must be greater than 0")),
+            per_page => per_page.unwrap_or(10),
+        };
+        let title = get_arc_str_arg(args, "title").unwrap_or_default();
+        let description = get_arc_str_arg(args, "description").unwrap_or_default();
+
+        let languages: Vec<Option<Arc<str>>> = match get_arc_str_arg(args, "lang") {
+            Some(lang) => vec![Some(lang)],
+            // one pagination per language when the pattern asks for it
+            None if pattern.contains("{lang}") && !pages.languages().is_empty() => {
+                pages.languages().into_iter().map(Some).collect()
+            }
+            None => vec![None],
+        };
+
+        let mut paths: Vec<Arc<str>> = vec![];
+        for lang in languages {
+            let view = pages.in_language(lang.as_deref()).ok_or_else(|| {
+                tera::Error::msg(format!(
+                    "language '{}' not found",
+                    lang.as_deref().unwrap_or_default()
+                ))
+            })?;
+            // the default language is published without prefix
+            let prefix = lang
+                .as_deref()
+                .filter(|lang| *lang != config.language.as_ref())
+                .unwrap_or_default();
+            let tags: Vec<Option<Arc<str>>> = match get_arc_str_arg(args, "tag") {
+                Some(tag) => vec![Some(tag)],
+                // one pagination per tag when the pattern asks for it
+                None if pattern.contains("{tag}") => {
+                    view.get_tags().into_iter().sorted().map(Some).collect()
+                }
+                None => vec![None],
+            };
+
+            for tag in tags {
+                let articles = view.get_articles(tag.as_deref()).ok_or_else(|| {
+                    tera::Error::msg(format!(
+                        "tag '{}' not found",
+                        tag.as_deref().unwrap_or_default()
+                    ))
+                })?;
+                let slug = tag.as_deref().map(tag_slug).unwrap_or_default();
+                let path_of = |n: usize| -> Arc<str> {
+                    let path = match &first_path {
+                        Some(first) if n == 0 => first.clone(),
+                        _ => pattern.replace("{n}", &(n + 1).to_string()),
+                    }
+                    .replace("{lang}", prefix)
+                    .replace("{tag}", &slug);
+                    // an empty `{lang}` leaves a double slash behind
+                    path.split("//").collect::<Vec<_>>().join("/").into()
+                };
+                let total = articles.len().div_ceil(per_page).max(1);
+                for page_num in 0..total {
+                    let path = path_of(page_num);
+                    let page = DynamicPage {
+                        path: path.clone(),
+                        template: template.clone(),
+                        title: title.clone(),
+                        description: description.clone(),
+                        content: None,
+                        page_num,
+                        pagination: Some(Pagination {
+                            tag: tag.clone(),
+                            lang: lang.clone(),
+                            per_page,
+                            prev: page_num.checked_sub(1).map(path_of),
+                            next: (page_num + 1 < total).then(|| path_of(page_num + 1)),
+                        }),
+                        record: None,
+                    };
+                    add_paginated_page(&site, page)?;
+                    paths.push(path);
+                }
+            }
+        }
+        Ok(tera::to_value(paths)?)
+    }
+}
+
+/// Register a page of `paginate`. Registering the same slice again is a no-op, and the first
+/// page takes over a plain page of the same template at its file, e.g. `/`.
+fn add_paginated_page(site: &Site, mut page: DynamicPage) -> Result<()> {
+    let pagination = page.pagination.as_ref();
+    match site
+        .get_page_by_file(&dynamic_page_file(&page.path))
+        .as_deref()
+    {
+        None => site.add_page(page.into()),
+        // the template is rendered once per page and paginates again each time
+        Some(Page::Dynamic(other))
+            if other.template == page.template
+                && other.page_num == page.page_num
+                && other.pagination.as_ref().is_some_and(|other| {
+                    pagination.is_some_and(|page| other.tag == page.tag && other.lang == page.lang)
+                }) => {}
+        Some(Page::Dynamic(other))
+            if page.page_num == 0
+                && other.template == page.template
+                && other.pagination.is_none()
+                && other.record.is_none() =>
+        {
+            page.path = other.path.clone();
+            if page.title.is_empty() {
+                page.title = other.title.clone();
+            }
+            if page.description.is_empty() {
+                page.description = other.description.clone();
+            }
+            site.replace_page(page.into());
+        }
+        Some(other) => {
+            return Err(tera::Error::msg(format!(
+                "paginated page {} collides with {}",
+                page.path,
+                other.get_path()
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn add_static_file(site: Arc<Site>, project_path: Arc<PathBuf>) -> impl Function + 'static {
     move |args: &HashMap<String, Value>| {
         let path = get_arc_str_arg(args, "path")
@@ -221,6 +504,7 @@ fn add_feed(site: Arc<Site>) -> impl Function + 'static {
                 path: path.clone(),
                 typ,
                 content: None,
+                lang: get_arc_str_arg(args, "lang"),
             }
             .into(),
         );
@@ -252,22 +536,330 @@ fn get_static_file(site: Arc<Site>) -> impl Function + 'static {
 
 fn get_pages_by_tag(pages: Arc<Pages>) -> impl Function + 'static {
     move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
         let tag = get_string_arg(args, "tag").unwrap_or_default();
-        let limit = get_usize_arg(args, "limit").unwrap_or(3);
+        let limit = get_limit_arg(args)?;
         let offset = get_usize_arg(args, "offset").unwrap_or(0);
-        let pages = pages
+        let pages = view
             .get_posts_by_tag(tag.as_str(), limit, offset)
             .ok_or_else(|| tera::Error::msg(format!("tag '{tag}' not found")))?;
         Ok(tera::to_value(pages)?)
     }
 }
 
+fn get_tag(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let tag = get_string_arg(args, "tag")
+            .ok_or_else(|| tera::Error::msg("tag is required for get_tag"))?;
+        let info = view
+            .get_tag(&tag)
+            .ok_or_else(|| tera::Error::msg(format!("tag '{tag}' not found")))?;
+        Ok(tera::to_value(info)?)
+    }
+}
+
+fn get_tags(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let tags: Vec<_> = view
+            .get_tags()
+            .into_iter()
+            .sorted()
+            .filter_map(|tag| view.get_tag(&tag))
+            .collect();
+        Ok(tera::to_value(tags)?)
+    }
+}
+
+fn t(i18n: Arc<I18n>, config: Arc<crate::config::Config>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let key =
+            get_string_arg(args, "key").ok_or_else(|| tera::Error::msg("key is required for t"))?;
+        let lang = get_string_arg(args, "lang").unwrap_or_else(|| {
+            i18n.page_language()
+                .unwrap_or_else(|| config.language.clone())
+                .to_string()
+        });
+        let count = args.get("count").and_then(Value::as_i64);
+        let text = i18n.translate(&key, &lang, count).ok_or_else(|| {
+            tera::Error::msg(format!(
+                "translation '{key}' not found for '{lang}' or default language '{}'",
+                i18n.default_language()
+            ))
+        })?;
+        Ok(Value::String(text))
+    }
+}
+
+fn get_data(data: Arc<Data>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let path = get_string_arg(args, "path")
+            .ok_or_else(|| tera::Error::msg("path is required for get_data"))?;
+        data.get(&path)
+            .cloned()
+            .ok_or_else(|| tera::Error::msg(format!("data file '{path}' not found in {DATA_DIR}/")))
+    }
+}
+
+fn get_author(config: Arc<crate::config::Config>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let id = get_string_arg(args, "id")
+            .ok_or_else(|| tera::Error::msg("id is required for get_author"))?;
+        let author = config
+            .authors
+            .get(id.as_str())
+            .ok_or_else(|| tera::Error::msg(format!("author '{id}' not found")))?;
+        let mut val = tera::to_value(author)?;
+        if let Some(obj) = val.as_object_mut() {
+            obj.insert("id".into(), id.into());
+        }
+        Ok(val)
+    }
+}
+
+fn get_pages_by_author(
+    pages: Arc<Pages>,
+    config: Arc<crate::config::Config>,
+) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let author = get_string_arg(args, "author")
+            .ok_or_else(|| tera::Error::msg("author is required for get_pages_by_author"))?;
+        if !config.authors.contains_key(author.as_str()) {
+            return Err(tera::Error::msg(format!("author '{author}' not found")));
+        }
+        let limit = get_limit_arg(args)?;
+        let offset = get_usize_arg(args, "offset").unwrap_or(0);
+        let slice = view
+            .get_posts_by_author(author.as_str(), limit, offset)
+            .unwrap_or_else(|| PagesSlice::new(&BTreeSet::new(), limit, offset));
+        Ok(tera::to_value(slice)?)
+    }
+}
+
+fn get_pages(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let fields = match (get_arc_str_arg(args, "field"), args.get("value")) {
+            (Some(field), Some(value)) => vec![(field, value.clone())],
+            (None, None) => vec![],
+            _ => {
+                return Err(tera::Error::msg(
+                    "field and value must be used together in get_pages",
+                ));
+            }
+        };
+        let ascending = match get_string_arg(args, "order").as_deref() {
+            None | Some("desc") => false,
+            Some("asc") => true,
+            Some(order) => {
+                return Err(tera::Error::msg(format!(
+                    "order must be 'asc' or 'desc', got '{order}'"
+                )));
+            }
+        };
+        let query = PageQuery {
+            any_tags: get_str_list_arg(args, "any_tags"),
+            all_tags: get_str_list_arg(args, "all_tags"),
+            exclude_tags: get_str_list_arg(args, "exclude_tags"),
+            from: get_date_arg(args, "from")?,
+            to: get_date_arg(args, "to")?,
+            fields,
+            sort_by: get_arc_str_arg(args, "sort_by"),
+            ascending,
+        };
+        let limit = get_limit_arg(args)?;
+        let offset = get_usize_arg(args, "offset").unwrap_or(0);
+        let found = view.query(&query);
+        Ok(tera::to_value(PagesSlice::new(&found, limit, offset))?)
+    }
+}
+
+fn get_section(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let section = get_string_arg(args, "section")
+            .ok_or_else(|| tera::Error::msg("section is required for get_section"))?;
+        let info = view
+            .get_section(&section)
+            .ok_or_else(|| tera::Error::msg(format!("section '{section}' not found")))?;
+        Ok(tera::to_value(info)?)
+    }
+}
+
+fn get_subsections(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let section = get_string_arg(args, "section").unwrap_or("/".to_string());
+        let info = view
+            .get_section(&section)
+            .ok_or_else(|| tera::Error::msg(format!("section '{section}' not found")))?;
+        let subsections: Vec<_> = info
+            .subsections
+            .iter()
+            .filter_map(|key| view.get_section(key))
+            .collect();
+        Ok(tera::to_value(subsections)?)
+    }
+}
+
+fn get_section_pages(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let section = get_string_arg(args, "section")
+            .ok_or_else(|| tera::Error::msg("section is required for get_section_pages"))?;
+        let limit = get_limit_arg(args)?;
+        let offset = get_usize_arg(args, "offset").unwrap_or(0);
+        let slice = view
+            .get_posts_by_section(&section, limit, offset)
+            .ok_or_else(|| tera::Error::msg(format!("section '{section}' not found")))?;
+        Ok(tera::to_value(slice)?)
+    }
+}
+
+fn get_pages_by_category(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let category = get_string_arg(args, "category")
+            .ok_or_else(|| tera::Error::msg("category is required for get_pages_by_category"))?;
+        let limit = get_limit_arg(args)?;
+        let offset = get_usize_arg(args, "offset").unwrap_or(0);
+        let slice = view
+            .get_posts_by_category(&category, limit, offset)
+            .ok_or_else(|| tera::Error::msg(format!("category '{category}' not found")))?;
+        Ok(tera::to_value(slice)?)
+    }
+}
+
+fn get_category_tree(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let root = get_string_arg(args, "root");
+        Ok(tera::to_value(view.get_category_tree(root.as_deref()))?)
+    }
+}
+
+fn get_category_breadcrumbs(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let category = get_string_arg(args, "category")
+            .ok_or_else(|| tera::Error::msg("category is required for get_category_breadcrumbs"))?;
+        let crumbs = view
+            .get_category_breadcrumbs(&category)
+            .ok_or_else(|| tera::Error::msg(format!("category '{category}' not found")))?;
+        Ok(tera::to_value(crumbs)?)
+    }
+}
+
+fn get_archive(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        Ok(tera::to_value(view.get_archive())?)
+    }
+}
+
+fn get_pages_by_period(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let view = pages_in(&pages, args)?;
+        let year = args
+            .get("year")
+            .and_then(Value::as_i64)
+            .and_then(|year| i32::try_from(year).ok())
+            .ok_or_else(|| tera::Error::msg("year is required for get_pages_by_period"))?;
+        let month = get_usize_arg(args, "month").and_then(|month| u32::try_from(month).ok());
+        let limit = get_limit_arg(args)?;
+        let offset = get_usize_arg(args, "offset").unwrap_or(0);
+        let slice = view
+            .get_posts_by_period(year, month, limit, offset)
+            .unwrap_or_else(|| PagesSlice::new(&BTreeSet::new(), limit, offset));
+        Ok(tera::to_value(slice)?)
+    }
+}
+
+fn get_series(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let series = get_string_arg(args, "series")
+            .ok_or_else(|| tera::Error::msg("series is required for get_series"))?;
+        let pages = pages_in(&pages, args)?
+            .get_series(&series)
+            .ok_or_else(|| tera::Error::msg(format!("series '{series}' not found")))?;
+        Ok(tera::to_value(pages)?)
+    }
+}
+
+fn get_series_nav(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let pid = get_string_arg(args, "pid")
+            .ok_or_else(|| tera::Error::msg("pid is required for get_series_nav"))?;
+        Ok(tera::to_value(
+            page_language(&pages, &pid).get_series_nav(&pid),
+        )?)
+    }
+}
+
+fn get_translation(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let pid = get_string_arg(args, "pid")
+            .ok_or_else(|| tera::Error::msg("pid is required for get_translation"))?;
+        let lang = get_string_arg(args, "lang")
+            .ok_or_else(|| tera::Error::msg("lang is required for get_translation"))?;
+        Ok(tera::to_value(pages.get_translation(&pid, &lang))?)
+    }
+}
+
+fn get_alternates(
+    pages: Arc<Pages>,
+    config: Arc<crate::config::Config>,
+) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let pid = get_string_arg(args, "pid")
+            .ok_or_else(|| tera::Error::msg("pid is required for get_alternates"))?;
+        Ok(tera::to_value(
+            pages.get_alternates(&pid, config.domain.as_ref()),
+        )?)
+    }
+}
+
+fn get_prev_page(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let pid = get_string_arg(args, "pid")
+            .ok_or_else(|| tera::Error::msg("pid is required for get_prev_page"))?;
+        let tag = get_string_arg(args, "tag");
+        let view = page_language(&pages, &pid);
+        Ok(tera::to_value(view.get_prev(&pid, tag.as_deref()))?)
+    }
+}
+
+fn get_next_page(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let pid = get_string_arg(args, "pid")
+            .ok_or_else(|| tera::Error::msg("pid is required for get_next_page"))?;
+        let tag = get_string_arg(args, "tag");
+        let view = page_language(&pages, &pid);
+        Ok(tera::to_value(view.get_next(&pid, tag.as_deref()))?)
+    }
+}
+
 fn get_similar(pages: Arc<Pages>) -> impl Function + 'static {
     move |args: &HashMap<String, Value>| {
         let pid = get_string_arg(args, "pid")
             .ok_or_else(|| tera::Error::msg("pid is required for get_similar"))?;
         let limit = get_usize_arg(args, "limit").unwrap_or(3);
-        Ok(tera::to_value(pages.get_similar(&pid, limit))?)
+        let similar = page_language(&pages, &pid).get_similar(&pid, limit);
+        if args.get("with_score").and_then(Value::as_bool) == Some(true) {
+            return Ok(tera::to_value(similar)?);
+        }
+        let pids = similar.into_iter().map(|similar| similar.pid);
+        Ok(tera::to_value(pids.collect::<Vec<_>>())?)
+    }
+}
+
+fn get_backlinks(pages: Arc<Pages>) -> impl Function + 'static {
+    move |args: &HashMap<String, Value>| {
+        let pid = get_string_arg(args, "pid")
+            .ok_or_else(|| tera::Error::msg("pid is required for get_backlinks"))?;
+        Ok(tera::to_value(pages.get_backlinks(&pid))?)
     }
 }
 
@@ -278,14 +870,15 @@ fn get_page_by_path(
     move |args: &HashMap<String, Value>| {
         let path = get_string_arg(args, "path")
             .ok_or_else(|| tera::Error::msg("path is required for get_page_by_path"))?;
-        let pid = path.trim_end_matches(".html");
-        let page = pages.get(pid);
+        let page = pages.get_by_path(&path);
         let mut val = tera::to_value(page)?;
-        if let Some(obj) = val.as_object_mut() {
+        if let (Some(obj), Some(page)) = (val.as_object_mut(), page) {
             let cache = rendered_cache.lock().expect("rendered cache poisoned");
-            if let Some(rendered) = cache.get(pid) {
+            if let Some(rendered) = cache.get(&page.pid) {
                 obj.insert("rendered_html".into(), rendered.html.clone().into());
                 obj.insert("rendered_css".into(), rendered.css.clone().into());
+                obj.insert("toc".into(), tera::to_value(&rendered.toc)?);
+                obj.insert("excerpt_html".into(), rendered.excerpt_html.clone().into());
             }
         }
         Ok(val)
@@ -306,6 +899,8 @@ fn get_page_by_pid(
             if let Some(rendered) = cache.get(pid.as_str()) {
                 obj.insert("rendered_html".into(), rendered.html.clone().into());
                 obj.insert("rendered_css".into(), rendered.css.clone().into());
+                obj.insert("toc".into(), tera::to_value(&rendered.toc)?);
+                obj.insert("excerpt_html".into(), rendered.excerpt_html.clone().into());
             }
         }
         Ok(val)
@@ -777,6 +1372,7 @@ fn crc32(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
     ))?)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn register_functions(
     tera: &mut Tera,
     site: Arc<Site>,
@@ -785,6 +1381,8 @@ pub fn register_functions(
     pages: &Arc<Pages>,
     rendered_cache: RenderedContentCache,
     image_widths: Arc<Vec<usize>>,
+    i18n: Arc<I18n>,
+    data: Arc<Data>,
 ) {
     let image_cache = ImageCache::new();
     tera.register_function("add_feed", add_feed(site.clone()));
@@ -822,6 +1420,14 @@ pub fn register_functions(
             image_widths,
         ),
     );
+    tera.register_function("t", t(i18n, config.clone()));
+    tera.register_function("get_data", get_data(data.clone()));
+    tera.register_function("add_data_pages", add_data_pages(site.clone(), data));
+    tera.register_function("get_author", get_author(config.clone()));
+    tera.register_function(
+        "get_pages_by_author",
+        get_pages_by_author(pages.clone(), config.clone()),
+    );
     tera.register_function("get_pages_by_tag", get_pages_by_tag(pages.clone()));
     tera.register_function(
         "get_page_by_path",
@@ -831,7 +1437,38 @@ pub fn register_functions(
         "get_page_by_pid",
         get_page_by_pid(pages.clone(), rendered_cache),
     );
+    tera.register_function(
+        "paginate",
+        paginate(site.clone(), pages.clone(), config.clone()),
+    );
+    tera.register_function("get_pages", get_pages(pages.clone()));
+    tera.register_function("get_tag", get_tag(pages.clone()));
+    tera.register_function("get_tags", get_tags(pages.clone()));
+    tera.register_function("get_section", get_section(pages.clone()));
+    tera.register_function("get_subsections", get_subsections(pages.clone()));
+    tera.register_function("get_section_pages", get_section_pages(pages.clone()));
+    tera.register_function(
+        "get_pages_by_category",
+        get_pages_by_category(pages.clone()),
+    );
+    tera.register_function("get_category_tree", get_category_tree(pages.clone()));
+    tera.register_function(
+        "get_category_breadcrumbs",
+        get_category_breadcrumbs(pages.clone()),
+    );
+    tera.register_function("get_archive", get_archive(pages.clone()));
+    tera.register_function("get_pages_by_period", get_pages_by_period(pages.clone()));
+    tera.register_function("get_series", get_series(pages.clone()));
+    tera.register_function("get_series_nav", get_series_nav(pages.clone()));
+    tera.register_function("get_translation", get_translation(pages.clone()));
+    tera.register_function(
+        "get_alternates",
+        get_alternates(pages.clone(), config.clone()),
+    );
+    tera.register_function("get_prev_page", get_prev_page(pages.clone()));
+    tera.register_function("get_next_page", get_next_page(pages.clone()));
     tera.register_function("get_similar", get_similar(pages.clone()));
+    tera.register_function("get_backlinks", get_backlinks(pages.clone()));
     tera.register_function("get_static_file", get_static_file(site.clone()));
     tera.register_function("render_gpx", render_gpx(site, config, project_path));
     tera.register_filter("crc32", crc32);
@@ -866,6 +1503,8 @@ pub fn initialize(
         &ctx.pages,
         rendered_cache,
         image_widths,
+        ctx.i18n.clone(),
+        ctx.data.clone(),
     );
     info!("template initialization complete");
     Ok(tera)
@@ -874,9 +1513,15 @@ pub fn initialize(
 #[cfg(test)]
 mod tests {
     use super::{
-        ImageCache, VariantSpec, get_transformations, image_variant, resolve_in_project, srcset_for,
+        Function, ImageCache, Value, VariantSpec, add_data_pages, get_category_breadcrumbs,
+        get_category_tree, get_pages, get_section, get_section_pages, get_similar, get_subsections,
+        get_tag, get_transformations, image_variant, paginate, resolve_in_project, srcset_for, t,
     };
-    use crate::site::{Page, Site};
+    use crate::i18n::I18n;
+    use crate::metadata::Metadata;
+    use crate::pages::{self, Pages};
+    use crate::site::{DynamicPage, Page, Site};
+    use std::collections::HashMap;
     use std::path::Path;
     use std::sync::Arc;
 
@@ -900,6 +1545,331 @@ mod tests {
         assert!(resolve_in_project(root, "a/../../b").is_err());
     }
 
+    #[test]
+    fn get_similar_stays_in_page_language() {
+        let mut pages = Pages::new();
+        for (pid, lang) in [("/post", "en"), ("/post.de", "de"), ("/other", "en")] {
+            pages.push(pages::Page::new(
+                pid.into(),
+                vec![],
+                String::new(),
+                Metadata {
+                    lang: Some(lang.into()),
+                    tags: Some(vec!["alps".into()]),
+                    ..Metadata::default()
+                },
+            ));
+        }
+        pages.split_languages(|_, section| section.to_string());
+        let get_similar = get_similar(Arc::new(pages));
+        let args = [("pid".to_string(), "/post".into())].into_iter().collect();
+        assert_eq!(
+            get_similar.call(&args).unwrap(),
+            serde_json::json!(["/other"])
+        );
+    }
+
+    #[test]
+    fn section_and_taxonomy_functions_use_lang_view() {
+        let mut pages = Pages::new();
+        for (pid, lang) in [
+            ("/travel/alps", "en"),
+            ("/travel/alps.de", "de"),
+            ("/travel/paris", "en"),
+        ] {
+            pages.push(pages::Page::new(
+                pid.into(),
+                vec![],
+                String::new(),
+                Metadata {
+                    lang: Some(lang.into()),
+                    tags: Some(vec!["alps".into()]),
+                    categories: Some(vec!["travel/europe".into()]),
+                    ..Metadata::default()
+                },
+            ));
+        }
+        pages.split_languages(|lang, section| format!("/{lang}{section}"));
+        let pages = Arc::new(pages);
+        let args = |pairs: &[(&str, &str)]| -> HashMap<String, Value> {
+            pairs
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).into()))
+                .collect()
+        };
+        let de = [("lang", "de")];
+        let pids = |value: Value| {
+            value["pages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|page| page["pid"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let section_pages = get_section_pages(pages.clone());
+        let all = section_pages
+            .call(&args(&[("section", "/travel")]))
+            .unwrap();
+        assert_eq!(pids(all).len(), 3);
+        let german = section_pages
+            .call(&args(&[("section", "/travel"), ("lang", "de")]))
+            .unwrap();
+        assert_eq!(pids(german), ["/travel/alps.de"]);
+
+        let section = get_section(pages.clone())
+            .call(&args(&[("section", "/travel"), ("lang", "de")]))
+            .unwrap();
+        assert_eq!(section["path"], "/de/travel");
+        assert_eq!(
+            get_subsections(pages.clone()).call(&args(&de)).unwrap()[0]["path"],
+            "/de/travel"
+        );
+        assert_eq!(
+            get_tag(pages.clone())
+                .call(&args(&[("tag", "alps"), ("lang", "de")]))
+                .unwrap()["count"],
+            1
+        );
+        assert_eq!(
+            get_category_tree(pages.clone()).call(&args(&de)).unwrap()[0]["count"],
+            1
+        );
+        assert!(
+            get_category_breadcrumbs(pages.clone())
+                .call(&args(&[("category", "travel/europe"), ("lang", "de")]))
+                .is_ok()
+        );
+        assert!(
+            get_section(pages)
+                .call(&args(&[("section", "/travel"), ("lang", "fr")]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn zero_limit_is_an_error() {
+        let get_pages = get_pages(Arc::new(Pages::new()));
+        let args = [("limit".to_string(), 0.into())].into_iter().collect();
+        let err = get_pages.call(&args).unwrap_err();
+        assert_eq!(err.to_string(), "limit must be greater than 0");
+
+        let paginate = paginate(
+            Arc::new(Site::new("/tmp".into())),
+            Arc::new(Pages::new()),
+            Arc::new(test_config()),
+        );
+        let args = [
+            ("template", "index.html".into()),
+            ("path_pattern", "/page/{n}.html".into()),
+            ("per_page", 0.into()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        let err = paginate.call(&args).unwrap_err();
+        assert_eq!(err.to_string(), "per_page must be greater than 0");
+    }
+
+    #[test]
+    fn add_data_pages_registers_page_per_record() {
+        let mut data = crate::data::Data::new();
+        data.add_file(
+            "hikes.csv",
+            "title,body\nMont Blanc,# Day 1\nEiger,\"Up, then down\"\n",
+        )
+        .unwrap();
+        data.add_file("dupes.yaml", "- title: a\n- title: A\n")
+            .unwrap();
+        data.add_file("more.yaml", "- title: Eiger\n").unwrap();
+        let site = Arc::new(Site::new("/tmp".into()));
+        let function = add_data_pages(site.clone(), Arc::new(data));
+        let args = |path: &str| {
+            [
+                ("path", path.into()),
+                ("template", "hike.html".into()),
+                ("path_pattern", "/hikes/{title}.html".into()),
+                ("body", "body".into()),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+        };
+
+        let paths = function.call(&args("hikes")).unwrap();
+        assert_eq!(
+            paths,
+            serde_json::json!(["/hikes/mont-blanc.html", "/hikes/eiger.html"])
+        );
+        let page = site.get_page("/hikes/eiger.html").expect("page");
+        let Page::Dynamic(page) = page.as_ref() else {
+            panic!("expected dynamic page");
+        };
+        assert_eq!(page.template.as_ref(), "hike.html");
+        assert_eq!(page.title.as_ref(), "Eiger");
+        let record = page.record.clone().expect("record");
+        assert_eq!(record.source.as_ref(), "data/hikes.csv#2");
+        assert_eq!(record.value["body"], "Up, then down");
+        assert_eq!(record.body.as_deref(), Some("body"));
+
+        assert_eq!(function.call(&args("hikes")).unwrap(), paths);
+        let err = function.call(&args("more")).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "more: record 1 at /hikes/eiger.html collides with record data/hikes.csv#2"
+        );
+        let err = function.call(&args("dupes")).unwrap_err().to_string();
+        assert!(err.contains("records 1 and 2"), "got: {err}");
+        assert!(function.call(&args("missing")).is_err());
+    }
+
+    #[test]
+    fn t_defaults_to_language_of_rendered_page() {
+        let mut i18n = I18n::new("en");
+        i18n.add_table("en", "en.toml", "read_more = \"Read more\"")
+            .unwrap();
+        i18n.add_table("de", "de.toml", "read_more = \"Weiterlesen\"")
+            .unwrap();
+        let i18n = Arc::new(i18n);
+        let mut pages = Pages::new();
+        pages.push(pages::Page::new(
+            "/post.de".into(),
+            vec![],
+            String::new(),
+            Metadata {
+                lang: Some("de".into()),
+                ..Metadata::default()
+            },
+        ));
+        let mut tera = tera::Tera::default();
+        tera.register_function("t", t(i18n.clone(), Arc::new(test_config())));
+        let render = |tera: &mut tera::Tera| {
+            tera.render_str("{{ t(key=\"read_more\") }}", &tera::Context::new())
+                .unwrap()
+        };
+
+        i18n.set_page_language(pages.get("/post.de").unwrap().metadata.lang.clone());
+        assert_eq!(render(&mut tera), "Weiterlesen");
+        i18n.set_page_language(None);
+        assert_eq!(render(&mut tera), "Read more");
+    }
+
+    fn test_config() -> crate::config::Config {
+        serde_yaml::from_str(
+            "dist_path: ./dist
+content_path: ./content
+static_source_path: ./public
+template: ./template
+domain: https://example.com
+title: t
+description: d
+template_config: {}
+yamd_processors: {}",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn paginate_registers_slices_per_tag() {
+        let mut pages = Pages::new();
+        for (pid, tags) in [
+            ("/a", vec!["Rust Lang"]),
+            ("/b", vec!["Rust Lang"]),
+            ("/c", vec!["go"]),
+        ] {
+            pages.push(pages::Page::new(
+                pid.into(),
+                vec![],
+                String::new(),
+                Metadata {
+                    tags: Some(tags.into_iter().map(Into::into).collect()),
+                    ..Metadata::default()
+                },
+            ));
+        }
+        let site = Arc::new(Site::new("/tmp".into()));
+        let function = paginate(site.clone(), Arc::new(pages), Arc::new(test_config()));
+        let args = [
+            ("template", "tag.html".into()),
+            ("path_pattern", "/tags/{tag}/page/{n}.html".into()),
+            ("first_path", "/tags/{tag}.html".into()),
+            ("per_page", 1.into()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+
+        let paths = function.call(&args).unwrap();
+        assert_eq!(
+            paths,
+            serde_json::json!([
+                "/tags/go.html",
+                "/tags/rust-lang.html",
+                "/tags/rust-lang/page/2.html"
+            ])
+        );
+        let page = site.get_page("/tags/rust-lang.html").expect("page");
+        let Page::Dynamic(first) = page.as_ref() else {
+            panic!("expected dynamic page");
+        };
+        let pagination = first.pagination.clone().expect("pagination");
+        assert_eq!(first.page_num, 0);
+        assert_eq!(pagination.tag.as_deref(), Some("rust lang"));
+        assert_eq!(pagination.prev, None);
+        assert_eq!(
+            pagination.next.as_deref(),
+            Some("/tags/rust-lang/page/2.html")
+        );
+    }
+
+    #[test]
+    fn paginate_first_path_reuses_home_and_rejects_collisions() {
+        let site = Arc::new(Site::new("/tmp".into()));
+        let home = |template: &str| DynamicPage {
+            path: "/".into(),
+            template: template.into(),
+            title: "Home".into(),
+            description: "".into(),
+            content: None,
+            page_num: 0,
+            pagination: None,
+            record: None,
+        };
+        site.add_page(home("index.html").into());
+        let function = paginate(
+            site.clone(),
+            Arc::new(Pages::new()),
+            Arc::new(test_config()),
+        );
+        let args = |first_path: &str| -> HashMap<String, tera::Value> {
+            [
+                ("template", "index.html".into()),
+                ("path_pattern", "/page/{n}.html".into()),
+                ("first_path", first_path.into()),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+        };
+
+        function.call(&args("/")).unwrap();
+        let page = site.get_page("/").expect("page");
+        let Page::Dynamic(first) = page.as_ref() else {
+            panic!("expected dynamic page");
+        };
+        assert!(first.pagination.is_some());
+        assert_eq!(first.title.as_ref(), "Home");
+        // rendering the page paginates again
+        function.call(&args("/index.html")).unwrap();
+
+        site.replace_page(home("home.html").into());
+        let err = function.call(&args("/index.html")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "paginated page /index.html collides with /"
+        );
+    }
+
     #[allow(clippy::cast_possible_truncation)]
     fn write_image(path: &Path, w: u32, h: u32, format: image::ImageFormat) {
         use image::{DynamicImage, Rgb, RgbImage};
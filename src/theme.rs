@@ -18,6 +18,13 @@ pub struct ThemeMeta {
     pub description: String,
     pub compatible_bar_versions: String,
     pub tags: Vec<String>,
+    /// Language of the theme's own strings, used when a translation is missing.
+    #[serde(default = "default_language")]
+    pub default_language: String,
+}
+
+fn default_language() -> String {
+    "en".to_string()
 }
 
 #[derive(Debug, Deserialize)]